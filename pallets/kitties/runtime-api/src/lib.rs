@@ -0,0 +1,17 @@
+//! Runtime API definition for the kitties pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+pub use pallet_kitties::MarketStats;
+
+sp_api::decl_runtime_apis! {
+	pub trait KittiesApi<BlockNumber, Balance> where
+		BlockNumber: Codec,
+		Balance: Codec,
+	{
+		/// Trade count, volume, floor and average price of the recent trades in the last
+		/// `window` blocks.
+		fn market_stats(window: BlockNumber) -> MarketStats<Balance>;
+	}
+}
@@ -4,9 +4,10 @@ use codec::HasCompact;
 use frame_support::{
 	pallet_prelude::*,
 	traits::{
-		tokens::ExistenceRequirement, BalanceStatus, Currency, Randomness, ReservableCurrency,
+		tokens::ExistenceRequirement, BalanceStatus, Currency, OnKilledAccount, Randomness,
+		ReservableCurrency, StorageVersion,
 	},
-	transactional,
+	transactional, PalletId,
 };
 use frame_system::pallet_prelude::*;
 /// Edit this file to define custom logic or remove it if it is not needed.
@@ -15,10 +16,19 @@ use frame_system::pallet_prelude::*;
 pub use pallet::*;
 use scale_info::TypeInfo;
 use sp_io::hashing::blake2_128;
-use sp_std::{borrow::ToOwned, convert::From, prelude::*};
+use sp_runtime::traits::{AccountIdConversion, IdentifyAccount, UniqueSaturatedInto, Verify, Zero};
+use sp_std::{
+	borrow::ToOwned,
+	convert::{From, TryInto},
+	prelude::*,
+};
 
 mod mock;
+mod proptests;
 mod tests;
+pub mod traits;
+
+pub use traits::{OnKittyBred, OnKittyBurned, OnKittyMinted, OnKittyTransferred};
 
 #[frame_support::pallet]
 pub mod pallet {
@@ -27,7 +37,7 @@ pub mod pallet {
 
 	#[cfg(feature = "std")]
 	use frame_support::serde::{Deserialize, Serialize};
-	use sp_runtime::traits::{AtLeast32BitUnsigned, CheckedAdd, One};
+	use sp_runtime::traits::{AtLeast32BitUnsigned, CheckedAdd, One, Saturating};
 
 	type AccountOf<T> = <T as frame_system::Config>::AccountId;
 	type BalanceOf<T> =
@@ -53,8 +63,145 @@ pub mod pallet {
 		Female,
 	}
 
+	// How the price of a Kitty changed in `PriceSet`.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub enum PriceChange {
+		Listed,
+		Repriced,
+		Delisted,
+		Expired,
+	}
+
+	// The operation applied by a batch call in `BatchCompleted`.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub enum BatchOperation {
+		Transfer,
+		SetPrice,
+		Create,
+	}
+
+	// A Kitty locked until the holder of its gift key signs a claim.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Gift<T: Config> {
+		pub kitty_id: T::KittyIndex,
+		pub sender: AccountOf<T>,
+		pub expiry: T::BlockNumber,
+	}
+
+	pub type RaffleId = u32;
+
+	pub type TournamentId = u32;
+
+	/// The rating of a Kitty which has not fought yet.
+	pub const INITIAL_RATING: u32 = 1200;
+
+	/// The maximum rating change of a single battle.
+	pub const ELO_K_FACTOR: u32 = 32;
+
+	// A completed Kitty sale.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Sale<T: Config> {
+		pub kitty_id: T::KittyIndex,
+		pub price: BalanceOf<T>,
+		pub block: T::BlockNumber,
+		pub buyer: AccountOf<T>,
+		pub seller: AccountOf<T>,
+	}
+
+	// Market figures over the recent trades within a window of blocks.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, Default)]
+	pub struct MarketStats<Balance> {
+		pub trades: u32,
+		pub volume: Balance,
+		pub floor_price: Option<Balance>,
+		pub average_price: Option<Balance>,
+	}
+
+	// Battle stats decoded from a Kitty's DNA.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, Default)]
+	pub struct KittyStats {
+		pub attack: u8,
+		pub defense: u8,
+		pub speed: u8,
+	}
+
+	impl KittyStats {
+		/// Each stat is the average of five DNA bytes.
+		pub fn from_dna(dna: &[u8; 16]) -> Self {
+			let stat = |genes: &[u8]| {
+				(genes.iter().map(|g| *g as u32).sum::<u32>() / genes.len() as u32) as u8
+			};
+			KittyStats {
+				attack: stat(&dna[0..5]),
+				defense: stat(&dna[5..10]),
+				speed: stat(&dna[10..15]),
+			}
+		}
+
+		/// Every level adds two points to each stat, up to level 50.
+		pub fn boosted(self, level: u32) -> Self {
+			let boost = (level.min(50) * 2) as u8;
+			KittyStats {
+				attack: self.attack.saturating_add(boost),
+				defense: self.defense.saturating_add(boost),
+				speed: self.speed.saturating_add(boost),
+			}
+		}
+	}
+
+	// Experience of a Kitty, checkpointed at `last_update`.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo, Default)]
+	pub struct Experience<BlockNumber> {
+		pub xp: u64,
+		pub level: u32,
+		pub last_update: BlockNumber,
+	}
+
+	// Win/loss record and Elo rating of a Kitty.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub struct BattleRecord {
+		pub wins: u32,
+		pub losses: u32,
+		pub rating: u32,
+	}
+
+	impl Default for BattleRecord {
+		fn default() -> Self {
+			BattleRecord { wins: 0, losses: 0, rating: INITIAL_RATING }
+		}
+	}
+
+	// Struct for holding a single-elimination tournament.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Tournament<T: Config> {
+		pub entry_fee: BalanceOf<T>,
+		pub start_block: T::BlockNumber,
+		// The entered kitties with the owner that paid the entry fee.
+		pub entrants: BoundedVec<(T::KittyIndex, AccountOf<T>), T::MaxTournamentEntrants>,
+	}
+
+	// Struct for holding a Kitty raffle.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct Raffle<T: Config> {
+		pub kitty_id: T::KittyIndex,
+		pub owner: AccountOf<T>,
+		pub ticket_price: BalanceOf<T>,
+		pub max_tickets: u32,
+		pub end_block: T::BlockNumber,
+		// One entry per ticket, an account may hold several tickets.
+		pub tickets: BoundedVec<AccountOf<T>, T::MaxRaffleTickets>,
+	}
+
+	/// The storage version, bumped when `OwnedKitties` was introduced.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	// Configure the pallet by specifying the parameters and types on which it depends.
@@ -78,8 +225,110 @@ pub mod pallet {
 		type Currency: ReservableCurrency<Self::AccountId>;
 
 		/// 作业 4
+		///
+		/// The initial pledge; once changed through `set_pledge` the stored value is used.
 		#[pallet::constant]
 		type Pledge: Get<BalanceOf<Self>>;
+
+		/// The origin which may change the pledge, e.g. root or a governance collective.
+		type PledgeOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The origin which may freeze, thaw and force-transfer kitties, and blacklist accounts.
+		type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The id of the account holding the kitties in the adoption pool.
+		#[pallet::constant]
+		type AdoptionPoolId: Get<PalletId>;
+
+		/// The reward an owner pays to whoever reprices the deposit of their kitty.
+		#[pallet::constant]
+		type RepriceReward: Get<BalanceOf<Self>>;
+
+		/// The number of tickets a raffle must sell for a winner to be drawn.
+		#[pallet::constant]
+		type MinRaffleTickets: Get<u32>;
+
+		/// The maximum number of tickets a raffle can sell.
+		///
+		/// Every raffle ending in a block is settled in `on_initialize`, so
+		/// `MaxRaffleTickets * MaxRafflesPerBlock` ticket refunds or payouts must fit in a block.
+		#[pallet::constant]
+		type MaxRaffleTickets: Get<u32>;
+
+		/// The maximum number of raffles ending in the same block, see `MaxRaffleTickets`.
+		#[pallet::constant]
+		type MaxRafflesPerBlock: Get<u32>;
+
+		/// The deposit reserved from the issuer of a challenge until it is accepted, withdrawn or
+		/// lapses.
+		#[pallet::constant]
+		type ChallengeDeposit: Get<BalanceOf<Self>>;
+
+		/// The maximum number of open challenges a Kitty can issue, and receive.
+		#[pallet::constant]
+		type MaxChallengesPerKitty: Get<u32>;
+
+		/// The origin which may schedule tournaments.
+		type TournamentOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The maximum number of kitties entering a tournament.
+		#[pallet::constant]
+		type MaxTournamentEntrants: Get<u32>;
+
+		/// The experience a Kitty gains for every block it is owned.
+		#[pallet::constant]
+		type ExperiencePerBlock: Get<u64>;
+
+		/// The experience needed for each level, in ascending order.
+		#[pallet::constant]
+		type LevelThresholds: Get<Vec<u64>>;
+
+		/// The blocks a level 0 Kitty must rest between breedings. Every level shortens it by
+		/// 10%, up to 50%.
+		#[pallet::constant]
+		type BreedCooldown: Get<Self::BlockNumber>;
+
+		/// The maximum number of listings expiring in the same block.
+		#[pallet::constant]
+		type MaxListingsExpiringPerBlock: Get<u32>;
+
+		/// The number of sales kept in the history of each Kitty.
+		#[pallet::constant]
+		type MaxSalesPerKitty: Get<u32>;
+
+		/// The number of sales kept in the global trade history.
+		#[pallet::constant]
+		type MaxRecentTrades: Get<u32>;
+
+		/// The signature over a transfer permit.
+		type PermitSignature: Verify<Signer = Self::PermitSigner> + Parameter;
+
+		/// The key that signs transfer permits, identifying the Kitty owner.
+		type PermitSigner: IdentifyAccount<AccountId = Self::AccountId>;
+
+		/// The priority of the unsigned transactions of this pallet.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// The maximum number of gifts expiring in the same block.
+		#[pallet::constant]
+		type MaxGiftsExpiringPerBlock: Get<u32>;
+
+		/// The maximum number of unsigned gift claims accepted in a block.
+		#[pallet::constant]
+		type MaxGiftClaimsPerBlock: Get<u32>;
+
+		/// Handler for newly minted kitties.
+		type OnKittyMinted: OnKittyMinted<Self::AccountId, Self::KittyIndex>;
+
+		/// Handler for kitties changing hands, it may veto the transfer.
+		type OnKittyTransferred: OnKittyTransferred<Self::AccountId, Self::KittyIndex>;
+
+		/// Handler for burned kitties.
+		type OnKittyBurned: OnKittyBurned<Self::AccountId, Self::KittyIndex>;
+
+		/// Handler for newly bred kitties.
+		type OnKittyBred: OnKittyBred<Self::AccountId, Self::KittyIndex>;
 	}
 
 	// Errors.
@@ -98,12 +347,96 @@ pub mod pallet {
 		NotKittyOwner,
 		/// Ensures the Kitty is for sale.
 		KittyNotForSale,
-		/// Ensures that the buying price is greater than the asking price.
+		/// Ensures that the asking price does not exceed the buyer's maximum price.
 		KittyBidPriceTooLow,
 		/// Ensures that an account has enough funds to purchase a Kitty.
 		NotEnoughBalance,
 		/// Handles arithemtic overflow when incrementing the Kitty counter.
 		KittyCntOverflow,
+		/// The deposit of the Kitty already matches the current pledge.
+		DepositUpToDate,
+		/// The owner of the Kitty was reaped, it can only be adopted.
+		KittyOrphaned,
+		/// The Kitty is not in the adoption pool.
+		KittyNotOrphaned,
+		/// The Kitty is locked in a raffle.
+		KittyInRaffle,
+		/// Handles checking whether the raffle exists.
+		RaffleNotExist,
+		/// The raffle must end in a future block.
+		RaffleEndInPast,
+		/// The raffle already ended.
+		RaffleEnded,
+		/// The number of tickets is outside the allowed range.
+		InvalidRaffleTickets,
+		/// All tickets of the raffle were sold.
+		RaffleSoldOut,
+		/// Too many raffles end in the requested block.
+		TooManyRafflesEnding,
+		/// Handles arithemtic overflow when incrementing the raffle counter.
+		RaffleIdOverflow,
+		/// A Kitty cannot battle a Kitty of the same owner.
+		CannotBattleOwnKitty,
+		/// Handles checking whether the challenge exists.
+		ChallengeNotExist,
+		/// The Kitty already challenged the opponent.
+		ChallengeExists,
+		/// One of the kitties has too many open challenges.
+		TooManyChallenges,
+		/// The Kitty is entered in a tournament.
+		KittyInTournament,
+		/// Handles checking whether the tournament exists.
+		TournamentNotExist,
+		/// The tournament must start in a future block.
+		TournamentStartInPast,
+		/// Another tournament already starts in the requested block.
+		TournamentSlotTaken,
+		/// The tournament already started.
+		TournamentStarted,
+		/// The tournament has no room for more kitties.
+		TournamentFull,
+		/// Handles arithemtic overflow when incrementing the tournament counter.
+		TournamentIdOverflow,
+		/// The Kitty bred too recently.
+		KittyBreedingCooldown,
+		/// The listing changed since the buyer looked at it.
+		ListingChanged,
+		/// A listing must expire in a future block.
+		ListingExpiryInPast,
+		/// Too many listings expire in the requested block.
+		TooManyListingsExpiring,
+		/// A batch must contain at least one item.
+		EmptyBatch,
+		/// A batch cannot contain more items than `MaxKittyOwned`.
+		BatchTooLarge,
+		/// The deadline of the permit passed.
+		PermitExpired,
+		/// The permit nonce is not the next nonce of the signer.
+		InvalidPermitNonce,
+		/// The permit is not signed by the Kitty owner.
+		InvalidPermitSignature,
+		/// The Kitty is locked in a gift.
+		KittyInGift,
+		/// A gift under the same key already exists.
+		GiftExists,
+		/// No unexpired gift matches the gift key.
+		GiftNotExist,
+		/// The claim is not signed by the gift key.
+		InvalidGiftSignature,
+		/// A gift must expire in a future block.
+		GiftExpiryInPast,
+		/// Too many gifts expire in the requested block.
+		TooManyGiftsExpiring,
+		/// The block already accepted `MaxGiftClaimsPerBlock` gift claims.
+		TooManyGiftClaims,
+		/// The Kitty is frozen by the `ForceOrigin`.
+		KittyFrozen,
+		/// The Kitty is not frozen.
+		KittyNotFrozen,
+		/// The account is blacklisted from trading.
+		AccountBlacklisted,
+		/// The account is not blacklisted.
+		AccountNotBlacklisted,
 	}
 
 	#[pallet::event]
@@ -111,14 +444,69 @@ pub mod pallet {
 	pub enum Event<T: Config> {
 		/// A new Kitty was sucessfully created. \[sender, kitty_id\]
 		Created(T::AccountId, T::KittyIndex),
-		/// Kitty price was sucessfully set. \[sender, kitty_id, new_price\]
-		PriceSet(T::AccountId, T::KittyIndex, Option<BalanceOf<T>>),
+		/// Kitty price was sucessfully set. \[sender, kitty_id, new_price, change\]
+		PriceSet(T::AccountId, T::KittyIndex, Option<BalanceOf<T>>, PriceChange),
 		/// A Kitty was sucessfully transferred. \[from, to, kitty_id\]
 		Transferred(T::AccountId, T::AccountId, T::KittyIndex),
-		/// A Kitty was sucessfully bought. \[buyer, seller, kitty_id, bid_price\]
+		/// A Kitty was sucessfully bought. \[buyer, seller, kitty_id, price\]
 		Bought(T::AccountId, T::AccountId, T::KittyIndex, BalanceOf<T>),
 		/// A new Kitty was sucessfully breed. \[sender, kitty_one, kitty_two, new_kitty\]
 		BreedKitty(T::AccountId, T::KittyIndex, T::KittyIndex, T::KittyIndex),
+		/// The pledge for each Kitty was changed. \[new_pledge\]
+		PledgeSet(BalanceOf<T>),
+		/// The deposit of a Kitty was brought in line with the pledge. \[kitty_id, old, new\]
+		DepositRepriced(T::KittyIndex, BalanceOf<T>, BalanceOf<T>),
+		/// The owner of a Kitty was reaped, the Kitty moved to the adoption pool.
+		/// \[kitty_id, old_owner\]
+		KittyOrphaned(T::KittyIndex, T::AccountId),
+		/// An orphaned Kitty was adopted. \[who, kitty_id\]
+		KittyAdopted(T::AccountId, T::KittyIndex),
+		/// A raffle was started. \[owner, raffle_id, kitty_id\]
+		RaffleStarted(T::AccountId, RaffleId, T::KittyIndex),
+		/// A raffle ticket was bought. \[buyer, raffle_id\]
+		RaffleTicketBought(T::AccountId, RaffleId),
+		/// A raffle winner was drawn. \[raffle_id, winner, kitty_id, proceeds\]
+		RaffleDrawn(RaffleId, T::AccountId, T::KittyIndex, BalanceOf<T>),
+		/// A raffle ended without a winner, all tickets were refunded. \[raffle_id\]
+		RaffleCancelled(RaffleId),
+		/// A Kitty challenged another one. \[sender, kitty_id, opponent\]
+		ChallengeIssued(T::AccountId, T::KittyIndex, T::KittyIndex),
+		/// A challenge was withdrawn, its deposit refunded. \[sender, kitty_id, opponent\]
+		ChallengeWithdrawn(T::AccountId, T::KittyIndex, T::KittyIndex),
+		/// Two kitties battled. \[challenger, defender, winner\]
+		BattleFought(T::KittyIndex, T::KittyIndex, T::KittyIndex),
+		/// A tournament was scheduled. \[tournament_id, entry_fee, start_block\]
+		TournamentCreated(TournamentId, BalanceOf<T>, T::BlockNumber),
+		/// A Kitty entered a tournament. \[sender, tournament_id, kitty_id\]
+		TournamentEntered(T::AccountId, TournamentId, T::KittyIndex),
+		/// A tournament was won. \[tournament_id, kitty_id, owner, prize\]
+		TournamentWon(TournamentId, T::KittyIndex, T::AccountId, BalanceOf<T>),
+		/// A tournament had too few kitties, all entry fees were refunded. \[tournament_id\]
+		TournamentCancelled(TournamentId),
+		/// A Kitty reached a new level. \[kitty_id, level\]
+		LevelUp(T::KittyIndex, u32),
+		/// A Kitty was locked in a gift. \[sender, kitty_id, expiry\]
+		GiftCreated(T::AccountId, T::KittyIndex, T::BlockNumber),
+		/// A gift was claimed. \[sender, recipient, kitty_id\]
+		GiftClaimed(T::AccountId, T::AccountId, T::KittyIndex),
+		/// A gift expired unclaimed, the Kitty stays with its sender. \[sender, kitty_id\]
+		GiftExpired(T::AccountId, T::KittyIndex),
+		/// A gift was cancelled as its Kitty was frozen. \[sender, kitty_id\]
+		GiftCancelled(T::AccountId, T::KittyIndex),
+		/// A Kitty was burned and its deposit returned. \[owner, kitty_id\]
+		Burned(T::AccountId, T::KittyIndex),
+		/// A Kitty was frozen. \[kitty_id\]
+		KittyFrozen(T::KittyIndex),
+		/// A Kitty was thawed. \[kitty_id\]
+		KittyThawed(T::KittyIndex),
+		/// A Kitty was transferred by the `ForceOrigin`. \[from, to, kitty_id\]
+		ForceTransferred(T::AccountId, T::AccountId, T::KittyIndex),
+		/// An account was blacklisted from trading. \[who\]
+		AccountBlacklisted(T::AccountId),
+		/// An account was removed from the blacklist. \[who\]
+		AccountUnblacklisted(T::AccountId),
+		/// A batch call completed. \[sender, operation, count\]
+		BatchCompleted(T::AccountId, BatchOperation, u32),
 	}
 
 	// Storage items.
@@ -134,12 +522,247 @@ pub mod pallet {
 	pub(super) type Kitties<T: Config> =
 		StorageMap<_, Twox64Concat, T::KittyIndex, Kitty<T>, OptionQuery>;
 
+	#[pallet::type_value]
+	pub(super) fn DefaultPledge<T: Config>() -> BalanceOf<T> {
+		T::Pledge::get()
+	}
+
+	#[pallet::storage]
+	#[pallet::getter(fn pledge)]
+	/// The deposit reserved for every Kitty, defaults to `Config::Pledge`.
+	pub(super) type CurrentPledge<T: Config> =
+		StorageValue<_, BalanceOf<T>, ValueQuery, DefaultPledge<T>>;
+
 	#[pallet::storage]
 	#[pallet::getter(fn kitties_owned)]
 	/// Keeps track of what accounts own what Kitty.
 	pub(super) type KittiesOwned<T: Config> =
 		StorageMap<_, Twox64Concat, T::KittyIndex, T::AccountId, OptionQuery>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn listing_nonce)]
+	/// Bumped every time the price of a Kitty changes, so buyers can detect stale listings.
+	pub(super) type ListingNonce<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, u32, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn listing_expiry)]
+	/// The block at which the listing of a Kitty expires.
+	pub(super) type ListingExpiry<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, T::BlockNumber, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn expiring_listings)]
+	/// The listings expiring at each block, purged from `on_idle`.
+	pub(super) type ExpiringListings<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<T::KittyIndex, T::MaxListingsExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn next_expiry_block)]
+	/// The first block whose expiring listings were not purged yet, set by the first listing
+	/// with an expiry.
+	pub(super) type NextExpiryBlock<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn permit_nonce)]
+	/// The nonce the next transfer permit of an account must carry.
+	pub(super) type PermitNonces<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn kitty_sales)]
+	/// The last sales of each Kitty, oldest first.
+	pub(super) type KittySales<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::KittyIndex,
+		BoundedVec<Sale<T>, T::MaxSalesPerKitty>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn recent_trades)]
+	/// The last sales of all kitties, oldest first.
+	pub(super) type RecentTrades<T: Config> =
+		StorageValue<_, BoundedVec<Sale<T>, T::MaxRecentTrades>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn owned_kitties)]
+	/// Keeps track of the Kitties each account owns.
+	pub(super) type OwnedKitties<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BoundedVec<T::KittyIndex, T::MaxKittyOwned>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn adoption_pool)]
+	/// Kitties whose owner was reaped, waiting to be adopted.
+	pub(super) type AdoptionPool<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, (), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn reaped_owners)]
+	/// Reaped accounts whose kitties were not moved to the adoption pool yet.
+	pub(super) type ReapedOwners<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn next_raffle_id)]
+	/// The id of the next raffle.
+	pub(super) type NextRaffleId<T: Config> = StorageValue<_, RaffleId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn raffles)]
+	/// Stores the running raffles.
+	pub(super) type Raffles<T: Config> =
+		StorageMap<_, Twox64Concat, RaffleId, Raffle<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn kitty_raffle)]
+	/// The raffle each raffled Kitty is locked in.
+	pub(super) type KittyRaffle<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, RaffleId, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn raffles_ending)]
+	/// The raffles to draw at each block.
+	pub(super) type RafflesEnding<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<RaffleId, T::MaxRafflesPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn battle_records)]
+	/// The win/loss record and rating of each Kitty.
+	pub(super) type BattleRecords<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, BattleRecord, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn battle_nonce)]
+	/// Counts the battles fought, mixed into the battle randomness.
+	pub(super) type BattleNonce<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn challenges)]
+	/// Open challenges keyed by the challenged and the challenging Kitty, along with the
+	/// account that issued the challenge and its deposit.
+	pub(super) type Challenges<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		T::KittyIndex,
+		Twox64Concat,
+		T::KittyIndex,
+		(T::AccountId, BalanceOf<T>),
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn challenges_issued)]
+	/// The opponents each Kitty has open challenges against.
+	pub(super) type ChallengesIssued<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::KittyIndex,
+		BoundedVec<T::KittyIndex, T::MaxChallengesPerKitty>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn challenges_received)]
+	/// The kitties each Kitty has open challenges from.
+	pub(super) type ChallengesReceived<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::KittyIndex,
+		BoundedVec<T::KittyIndex, T::MaxChallengesPerKitty>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn next_tournament_id)]
+	/// The id of the next tournament.
+	pub(super) type NextTournamentId<T: Config> = StorageValue<_, TournamentId, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn tournaments)]
+	/// Stores the scheduled tournaments.
+	pub(super) type Tournaments<T: Config> =
+		StorageMap<_, Twox64Concat, TournamentId, Tournament<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn tournaments_starting)]
+	/// The tournament to run at each block.
+	pub(super) type TournamentsStarting<T: Config> =
+		StorageMap<_, Twox64Concat, T::BlockNumber, TournamentId, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn kitty_experience)]
+	/// The last experience checkpoint of each Kitty.
+	pub(super) type KittyExperience<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, Experience<T::BlockNumber>, ValueQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn last_bred)]
+	/// The block each Kitty last bred at.
+	pub(super) type LastBred<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, T::BlockNumber, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn kitty_tournament)]
+	/// The tournament each entered Kitty is locked in.
+	pub(super) type KittyTournament<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, TournamentId, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn frozen_kitties)]
+	/// Kitties frozen by the `ForceOrigin`.
+	pub(super) type FrozenKitties<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, (), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn blacklist)]
+	/// Accounts blacklisted from trading.
+	pub(super) type Blacklist<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn gifts)]
+	/// Pending gifts, keyed by the public key that signs their claim.
+	pub(super) type Gifts<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, Gift<T>, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn kitty_gift)]
+	/// The gift each Kitty is locked in.
+	pub(super) type KittyGift<T: Config> =
+		StorageMap<_, Twox64Concat, T::KittyIndex, T::AccountId, OptionQuery>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn gifts_expiring)]
+	/// The gifts expiring in each block.
+	pub(super) type GiftsExpiring<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<T::AccountId, T::MaxGiftsExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	/// The block of the last gift claim and the number of claims in that block.
+	pub(super) type GiftClaims<T: Config> = StorageValue<_, (T::BlockNumber, u32), ValueQuery>;
+
 	// Our pallet's genesis configuration.
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
@@ -166,7 +789,121 @@ pub mod pallet {
 	}
 
 	#[pallet::hooks]
-	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let ending = RafflesEnding::<T>::take(n);
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
+			for raffle_id in ending.into_inner() {
+				// Settling moves the reserved price of each ticket, touching two accounts.
+				let tickets = Self::settle_raffle(raffle_id) as Weight;
+				weight = weight.saturating_add(
+					T::DbWeight::get().reads_writes(8 + 2 * tickets, 8 + 2 * tickets),
+				);
+			}
+
+			weight = weight.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			if let Some(tournament_id) = TournamentsStarting::<T>::take(n) {
+				let entrants = Self::run_tournament(tournament_id) as Weight;
+				weight = weight.saturating_add(
+					T::DbWeight::get().reads_writes(2 + 8 * entrants, 2 + 8 * entrants),
+				);
+			}
+
+			let expiring = GiftsExpiring::<T>::take(n);
+			let gifts = expiring.len() as Weight;
+			weight =
+				weight.saturating_add(T::DbWeight::get().reads_writes(1 + gifts, 1 + 2 * gifts));
+			for gift_key in expiring.into_inner() {
+				Self::expire_gift(&gift_key, n);
+			}
+			weight
+		}
+
+		fn on_idle(n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let used = Self::orphan_reaped_kitties(remaining_weight);
+			used.saturating_add(Self::purge_expired_listings(
+				n,
+				remaining_weight.saturating_sub(used),
+			))
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			Self::migrate_owned_kitties()
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade() -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let now = <frame_system::Pallet<T>>::block_number();
+			match call {
+				Call::transfer_with_permit { from, to, kitty_id, deadline, nonce, signature } => {
+					if *nonce > Self::permit_nonce(from) {
+						return InvalidTransaction::Future.into()
+					}
+					Self::check_permit(from, to, kitty_id, *deadline, *nonce, signature).map_err(
+						|e| match e {
+							Error::<T>::InvalidPermitSignature => InvalidTransaction::BadProof,
+							_ => InvalidTransaction::Stale,
+						},
+					)?;
+					if !matches!(Self::is_kitty_owner(kitty_id, from), Ok(true)) {
+						return InvalidTransaction::Call.into()
+					}
+					// A transfer failing at dispatch would roll the nonce back and stay valid.
+					let transferable = from != to &&
+						Self::ensure_kitty_unlocked(kitty_id)
+							.and_then(|_| Self::ensure_not_blacklisted(from))
+							.and_then(|_| Self::ensure_not_blacklisted(to))
+							.is_ok();
+					if !transferable {
+						return InvalidTransaction::Call.into()
+					}
+
+					ValidTransaction::with_tag_prefix("KittiesPermit")
+						.priority(T::UnsignedPriority::get())
+						.and_provides((from, nonce))
+						.longevity(deadline.saturating_sub(now).unique_saturated_into())
+						.propagate(true)
+						.build()
+				},
+				Call::claim_gift { gift_key, recipient, signature } => {
+					let gift =
+						Self::check_gift(gift_key, recipient, signature).map_err(|e| match e {
+							Error::<T>::InvalidGiftSignature => InvalidTransaction::BadProof,
+							_ => InvalidTransaction::Stale,
+						})?;
+					Self::ensure_gift_claims_available()
+						.map_err(|_| InvalidTransaction::ExhaustsResources)?;
+					// A claim failing at dispatch would roll the claim count back and stay valid.
+					let claimable = gift.sender != *recipient &&
+						!FrozenKitties::<T>::contains_key(&gift.kitty_id) &&
+						Self::ensure_not_blacklisted(&gift.sender)
+							.and_then(|_| Self::ensure_not_blacklisted(recipient))
+							.is_ok();
+					if !claimable {
+						return InvalidTransaction::Call.into()
+					}
+
+					// One claim of a gift key in the pool at a time.
+					ValidTransaction::with_tag_prefix("KittiesGift")
+						.priority(T::UnsignedPriority::get())
+						.and_provides(gift_key)
+						.longevity(gift.expiry.saturating_sub(now).unique_saturated_into())
+						.propagate(true)
+						.build()
+				},
+				_ => InvalidTransaction::Call.into(),
+			}
+		}
+	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
 	// These functions materialize as "extrinsics", which are often compared to transactions.
@@ -191,30 +928,115 @@ pub mod pallet {
 			Ok(())
 		}
 
-		/// 重构代码
+		/// Create `count` new unique kitties.
+		///
+		/// Either every Kitty is created or none is.
+		#[transactional]
+		#[pallet::weight(100u64.saturating_mul(*count as u64))]
+		pub fn create_kitties(origin: OriginFor<T>, count: u32) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			Self::ensure_batch_size(count as usize)?;
+
+			for _ in 0..count {
+				let kitty_id = Self::mint(&sender, None, None)?;
+				Self::deposit_event(Event::Created(sender.clone(), kitty_id));
+			}
+
+			Self::deposit_event(Event::BatchCompleted(sender, BatchOperation::Create, count));
+			Ok(())
+		}
+
+		/// List a Kitty for sale or change its price.
+		///
+		/// The listing lapses at `expires_at`, if given.
+		#[transactional]
 		#[pallet::weight(100)]
 		pub fn sell_kitty(
 			origin: OriginFor<T>,
 			kitty_id: T::KittyIndex,
-			new_price: Option<BalanceOf<T>>,
+			new_price: BalanceOf<T>,
+			expires_at: Option<T::BlockNumber>,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
-			Self::exchange(&kitty_id, &sender, None, new_price)
+			if let Some(expiry) = expires_at {
+				ensure!(
+					expiry > <frame_system::Pallet<T>>::block_number(),
+					Error::<T>::ListingExpiryInPast
+				);
+			}
+
+			Self::exchange(&kitty_id, &sender, None, Some(new_price))?;
+
+			if let Some(expiry) = expires_at {
+				ExpiringListings::<T>::try_mutate(&expiry, |ids| ids.try_push(kitty_id))
+					.map_err(|_| Error::<T>::TooManyListingsExpiring)?;
+				ListingExpiry::<T>::insert(&kitty_id, expiry);
+				if Self::next_expiry_block().is_none() {
+					NextExpiryBlock::<T>::put(<frame_system::Pallet<T>>::block_number());
+				}
+			}
+			Ok(())
+		}
+
+		/// Take a Kitty off the market.
+		#[pallet::weight(100)]
+		pub fn cancel_listing(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+			// A lapsed listing waiting to be purged is no longer for sale either.
+			ensure!(
+				Self::kitties(&kitty_id)
+					.and_then(|k| Self::active_price(&kitty_id, &k))
+					.is_some(),
+				Error::<T>::KittyNotForSale
+			);
+			Self::exchange(&kitty_id, &sender, None, None)
+		}
+
+		/// Burn a Kitty, returning its deposit to the owner.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn burn_kitty(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+			Self::ensure_kitty_unlocked(&kitty_id)?;
+
+			let mut kitty = Kitties::<T>::take(&kitty_id).ok_or(Error::<T>::KittyNotExist)?;
+			Self::set_price(&kitty_id, &mut kitty, None);
+			T::Currency::unreserve(&sender, kitty.deposit);
+
+			Self::remove_owned_kitty(&sender, &kitty_id);
+			KittiesOwned::<T>::remove(&kitty_id);
+			ListingNonce::<T>::remove(&kitty_id);
+			KittySales::<T>::remove(&kitty_id);
+			KittyExperience::<T>::remove(&kitty_id);
+			LastBred::<T>::remove(&kitty_id);
+			BattleRecords::<T>::remove(&kitty_id);
+			Self::clear_challenges(&kitty_id);
+
+			T::OnKittyBurned::on_kitty_burned(&sender, &kitty_id);
+			Self::deposit_event(Event::Burned(sender, kitty_id));
+			Ok(())
 		}
 
 	
-		/// 重构代码
+		/// Buy a Kitty for its asking price.
+		///
+		/// Fails if the asking price exceeds `max_price`, or if the listing changed since
+		/// `listing_nonce` was read.
 		#[transactional]
 		#[pallet::weight(100)]
 		pub fn buy_kitty(
 			origin: OriginFor<T>,
 			kitty_id: T::KittyIndex,
-			bid_price: BalanceOf<T>,
+			max_price: BalanceOf<T>,
+			listing_nonce: u32,
 		) -> DispatchResult {
 			let buyer = ensure_signed(origin)?;
 			ensure!(!Self::is_kitty_owner(&kitty_id, &buyer)?, Error::<T>::BuyerIsKittyOwner);
-			Self::exchange(&kitty_id, &buyer, None, Some(bid_price))
+			ensure!(Self::listing_nonce(&kitty_id) == listing_nonce, Error::<T>::ListingChanged);
+			Self::exchange(&kitty_id, &buyer, None, Some(max_price))
 		}
 
 
@@ -234,38 +1056,608 @@ pub mod pallet {
 			Self::exchange(&kitty_id, &from, Some(to), None)
 		}
 
+		/// Transfer a Kitty on behalf of its owner, who signed a permit off-chain.
+		///
+		/// This is an unsigned transaction, so a relayer can submit it without the owner paying
+		/// any fee. The permit signs `permit_payload` and can be used once, before `deadline`.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn transfer_with_permit(
+			origin: OriginFor<T>,
+			from: T::AccountId,
+			to: T::AccountId,
+			kitty_id: T::KittyIndex,
+			deadline: T::BlockNumber,
+			nonce: u32,
+			signature: T::PermitSignature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::check_permit(&from, &to, &kitty_id, deadline, nonce, &signature)?;
+
+			PermitNonces::<T>::insert(&from, nonce.saturating_add(1));
+			ensure!(&from != &to, Error::<T>::TransferToSelf);
+			Self::exchange(&kitty_id, &from, Some(to), None)
+		}
 
-		/// Breed a Kitty.
+		/// Lock a Kitty as a gift for whoever holds the private half of `gift_key`.
 		///
-		/// Breed two kitties to create a new generation
-		/// of Kitties.
+		/// The Kitty stays with its owner if the gift is not claimed before `expiry`.
+		#[transactional]
 		#[pallet::weight(100)]
-		pub fn breed_kitty(
+		pub fn create_gift(
 			origin: OriginFor<T>,
-			kid1: T::KittyIndex,
-			kid2: T::KittyIndex,
+			kitty_id: T::KittyIndex,
+			gift_key: T::AccountId,
+			expiry: T::BlockNumber,
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+			Self::ensure_kitty_unlocked(&kitty_id)?;
+			Self::ensure_not_blacklisted(&sender)?;
+			ensure!(
+				expiry > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::GiftExpiryInPast
+			);
+			ensure!(!Gifts::<T>::contains_key(&gift_key), Error::<T>::GiftExists);
 
-			// Check: Verify `sender` owns both kitties (and both kitties exist).
-			ensure!(Self::is_kitty_owner(&kid1, &sender)?, Error::<T>::NotKittyOwner);
-			ensure!(Self::is_kitty_owner(&kid2, &sender)?, Error::<T>::NotKittyOwner);
+			GiftsExpiring::<T>::try_mutate(&expiry, |keys| keys.try_push(gift_key.clone()))
+				.map_err(|_| Error::<T>::TooManyGiftsExpiring)?;
 
-			let new_dna = Self::breed_dna(&kid1, &kid2)?;
-			let kitty_id = Self::mint(&sender, Some(new_dna), None)?;
-			// Deposit our "Breed" event.
-			Self::deposit_event(Event::BreedKitty(sender, kid1, kid2, kitty_id));
+			// A gifted kitty is no longer for sale.
+			Kitties::<T>::mutate(&kitty_id, |maybe| {
+				if let Some(k) = maybe {
+					Self::set_price(&kitty_id, k, None);
+				}
+			});
+			KittyGift::<T>::insert(&kitty_id, &gift_key);
+			Gifts::<T>::insert(gift_key, Gift::<T> { kitty_id, sender: sender.clone(), expiry });
+
+			Self::deposit_event(Event::GiftCreated(sender, kitty_id, expiry));
 			Ok(())
 		}
-	}
-
-	//** Our helper functions.**//
 
-	impl<T: Config> Pallet<T> {
-		fn gen_gender() -> Gender {
-			let random = T::KittyRandomness::random(&b"gender"[..]).0;
-			match random.as_ref()[0] % 2 {
-				0 => Gender::Male,
+		/// Claim a gift with a signature of the gift key over `gift_claim_payload`, sending the
+		/// Kitty and its deposit to `recipient`.
+		///
+		/// The signature binds the recipient, so a claim seen in the pool cannot be redirected.
+		/// This is an unsigned transaction so the recipient needs no balance, at most
+		/// `MaxGiftClaimsPerBlock` claims are accepted in a block.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn claim_gift(
+			origin: OriginFor<T>,
+			gift_key: T::AccountId,
+			recipient: T::AccountId,
+			signature: T::PermitSignature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let gift = Self::check_gift(&gift_key, &recipient, &signature)?;
+			Self::ensure_gift_claims_available()?;
+			ensure!(gift.sender != recipient, Error::<T>::TransferToSelf);
+			Self::ensure_not_blacklisted(&recipient)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			GiftClaims::<T>::mutate(|(block, count)| {
+				if *block != now {
+					*block = now;
+					*count = 0;
+				}
+				*count = count.saturating_add(1);
+			});
+			Gifts::<T>::remove(&gift_key);
+			KittyGift::<T>::remove(&gift.kitty_id);
+			Self::remove_expiring_gift(&gift_key, gift.expiry);
+			Self::ensure_kitty_unlocked(&gift.kitty_id)?;
+			Self::ensure_not_blacklisted(&gift.sender)?;
+
+			Kitties::<T>::try_mutate(&gift.kitty_id, |maybe| -> DispatchResult {
+				let k = maybe.as_mut().ok_or(Error::<T>::KittyNotExist)?;
+				Self::do_transfer(&gift.kitty_id, k, &recipient)
+			})?;
+
+			Self::deposit_event(Event::GiftClaimed(gift.sender, recipient, gift.kitty_id));
+			Ok(())
+		}
+
+		/// Transfer several kitties at once, each to its own recipient.
+		///
+		/// Either every transfer succeeds or none does.
+		#[transactional]
+		#[pallet::weight(100u64.saturating_mul(transfers.len() as u64))]
+		pub fn transfer_batch(
+			origin: OriginFor<T>,
+			transfers: Vec<(T::AccountId, T::KittyIndex)>,
+		) -> DispatchResult {
+			let from = ensure_signed(origin)?;
+			let count = Self::ensure_batch_size(transfers.len())?;
+
+			for (to, kitty_id) in transfers {
+				ensure!(&from != &to, Error::<T>::TransferToSelf);
+				Self::exchange(&kitty_id, &from, Some(to), None)?;
+			}
+
+			Self::deposit_event(Event::BatchCompleted(from, BatchOperation::Transfer, count));
+			Ok(())
+		}
+
+		/// List several kitties for sale or change their prices at once.
+		///
+		/// Either every price is set or none is.
+		#[transactional]
+		#[pallet::weight(100u64.saturating_mul(prices.len() as u64))]
+		pub fn set_prices_batch(
+			origin: OriginFor<T>,
+			prices: Vec<(T::KittyIndex, BalanceOf<T>)>,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let count = Self::ensure_batch_size(prices.len())?;
+
+			for (kitty_id, new_price) in prices {
+				ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+				Self::exchange(&kitty_id, &sender, None, Some(new_price))?;
+			}
+
+			Self::deposit_event(Event::BatchCompleted(sender, BatchOperation::SetPrice, count));
+			Ok(())
+		}
+
+
+		/// Breed a Kitty.
+		///
+		/// Breed two kitties to create a new generation
+		/// of Kitties.
+		#[pallet::weight(100)]
+		pub fn breed_kitty(
+			origin: OriginFor<T>,
+			kid1: T::KittyIndex,
+			kid2: T::KittyIndex,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			// Check: Verify `sender` owns both kitties (and both kitties exist).
+			ensure!(Self::is_kitty_owner(&kid1, &sender)?, Error::<T>::NotKittyOwner);
+			ensure!(Self::is_kitty_owner(&kid2, &sender)?, Error::<T>::NotKittyOwner);
+			Self::ensure_kitty_unlocked(&kid1)?;
+			Self::ensure_kitty_unlocked(&kid2)?;
+			ensure!(
+				Self::can_breed(&kid1) && Self::can_breed(&kid2),
+				Error::<T>::KittyBreedingCooldown
+			);
+
+			let new_dna = Self::breed_dna(&kid1, &kid2)?;
+			let kitty_id = Self::mint(&sender, Some(new_dna), None)?;
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			for parent in [kid1, kid2].iter() {
+				Self::checkpoint_experience(parent);
+				LastBred::<T>::insert(parent, now);
+			}
+			T::OnKittyBred::on_kitty_bred(&sender, (&kid1, &kid2), &kitty_id);
+			// Deposit our "Breed" event.
+			Self::deposit_event(Event::BreedKitty(sender, kid1, kid2, kitty_id));
+			Ok(())
+		}
+
+		/// Change the pledge reserved for each Kitty.
+		///
+		/// Existing kitties keep their deposit until `reprice_deposit` is called or they are
+		/// transferred.
+		#[pallet::weight(100)]
+		pub fn set_pledge(origin: OriginFor<T>, new_pledge: BalanceOf<T>) -> DispatchResult {
+			T::PledgeOrigin::ensure_origin(origin)?;
+
+			CurrentPledge::<T>::put(new_pledge);
+			Self::deposit_event(Event::PledgeSet(new_pledge));
+			Ok(())
+		}
+
+		/// Freeze a Kitty, taking it off the market and cancelling its pending gift. A frozen
+		/// Kitty cannot be traded, transferred or bred until it is thawed.
+		#[pallet::weight(100)]
+		pub fn freeze_kitty(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(!FrozenKitties::<T>::contains_key(&kitty_id), Error::<T>::KittyFrozen);
+
+			Kitties::<T>::try_mutate(&kitty_id, |maybe| -> DispatchResult {
+				let k = maybe.as_mut().ok_or(Error::<T>::KittyNotExist)?;
+				Self::set_price(&kitty_id, k, None);
+				Ok(())
+			})?;
+			FrozenKitties::<T>::insert(&kitty_id, ());
+			Self::cancel_gift(&kitty_id);
+
+			Self::deposit_event(Event::KittyFrozen(kitty_id));
+			Ok(())
+		}
+
+		/// Thaw a frozen Kitty.
+		#[pallet::weight(100)]
+		pub fn thaw_kitty(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(FrozenKitties::<T>::contains_key(&kitty_id), Error::<T>::KittyNotFrozen);
+
+			FrozenKitties::<T>::remove(&kitty_id);
+			Self::deposit_event(Event::KittyThawed(kitty_id));
+			Ok(())
+		}
+
+		/// Move a Kitty and its pledge to `to`, even if it is frozen or either account is
+		/// blacklisted.
+		///
+		/// A Kitty locked in the adoption pool, a raffle, a tournament or a gift cannot be moved,
+		/// and the `OnKittyTransferred` handler may still veto the transfer.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn force_transfer(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			to: T::AccountId,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			Self::ensure_kitty_idle(&kitty_id)?;
+
+			let from = Kitties::<T>::try_mutate(&kitty_id, |maybe| {
+				let k = maybe.as_mut().ok_or(Error::<T>::KittyNotExist)?;
+				let from = k.owner.clone();
+				ensure!(from != to, Error::<T>::TransferToSelf);
+				Self::do_transfer(&kitty_id, k, &to)?;
+				Ok::<_, DispatchError>(from)
+			})?;
+
+			Self::deposit_event(Event::ForceTransferred(from, to, kitty_id));
+			Ok(())
+		}
+
+		/// Blacklist an account from trading kitties.
+		#[pallet::weight(100)]
+		pub fn blacklist_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(!Blacklist::<T>::contains_key(&who), Error::<T>::AccountBlacklisted);
+
+			Blacklist::<T>::insert(&who, ());
+			Self::deposit_event(Event::AccountBlacklisted(who));
+			Ok(())
+		}
+
+		/// Remove an account from the blacklist.
+		#[pallet::weight(100)]
+		pub fn unblacklist_account(origin: OriginFor<T>, who: T::AccountId) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			ensure!(Blacklist::<T>::contains_key(&who), Error::<T>::AccountNotBlacklisted);
+
+			Blacklist::<T>::remove(&who);
+			Self::deposit_event(Event::AccountUnblacklisted(who));
+			Ok(())
+		}
+
+		/// Reserve or unreserve the difference between a Kitty's deposit and the current pledge.
+		///
+		/// Anyone may call this. When the caller is not the owner, the owner pays the caller
+		/// `RepriceReward`, capped at the repriced difference.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn reprice_deposit(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			// An orphan has no deposit until it is adopted.
+			Self::ensure_kitty_unlocked(&kitty_id)?;
+
+			Kitties::<T>::try_mutate(&kitty_id, |maybe| -> DispatchResult {
+				let k = maybe.as_mut().ok_or(Error::<T>::KittyNotExist)?;
+				let old_deposit = k.deposit;
+
+				ensure!(Self::reconcile_deposit(&kitty_id, k)?, Error::<T>::DepositUpToDate);
+
+				if sender != k.owner {
+					let diff = if old_deposit > k.deposit {
+						old_deposit.saturating_sub(k.deposit)
+					} else {
+						k.deposit.saturating_sub(old_deposit)
+					};
+					let reward = T::RepriceReward::get().min(diff);
+					T::Currency::transfer(
+						&k.owner,
+						&sender,
+						reward,
+						ExistenceRequirement::KeepAlive,
+					)?;
+				}
+				Ok(())
+			})
+		}
+
+		/// Adopt a Kitty whose owner was reaped by reserving the current pledge.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn adopt_kitty(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			// Taken first so the Kitty only passes as idle if nothing else locks it.
+			ensure!(AdoptionPool::<T>::take(&kitty_id).is_some(), Error::<T>::KittyNotOrphaned);
+			ensure!(!FrozenKitties::<T>::contains_key(&kitty_id), Error::<T>::KittyFrozen);
+			Self::ensure_kitty_idle(&kitty_id)?;
+			Self::ensure_not_blacklisted(&sender)?;
+
+			Kitties::<T>::try_mutate(&kitty_id, |maybe| -> DispatchResult {
+				let k = maybe.as_mut().ok_or(Error::<T>::KittyNotExist)?;
+
+				T::OnKittyTransferred::on_kitty_transferred(&k.owner, &sender, &kitty_id)?;
+				let deposit = Self::pledge();
+				T::Currency::reserve(&sender, deposit)?;
+				Self::add_owned_kitty(&sender, &kitty_id)?;
+
+				k.owner = sender.clone();
+				k.deposit = deposit;
+				k.price = None;
+				Ok(())
+			})?;
+
+			KittiesOwned::<T>::insert(&kitty_id, &sender);
+			// Experience accrues again from now on.
+			KittyExperience::<T>::mutate(&kitty_id, |exp| {
+				exp.last_update = <frame_system::Pallet<T>>::block_number();
+			});
+
+			Self::deposit_event(Event::KittyAdopted(sender, kitty_id));
+			Ok(())
+		}
+
+		/// Raffle a Kitty off.
+		///
+		/// The winner is drawn at `end_block` when at least `MinRaffleTickets` tickets were
+		/// sold, otherwise every ticket is refunded.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn start_raffle(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			ticket_price: BalanceOf<T>,
+			max_tickets: u32,
+			end_block: T::BlockNumber,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+			Self::ensure_kitty_unlocked(&kitty_id)?;
+			Self::ensure_not_blacklisted(&sender)?;
+			ensure!(
+				end_block > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::RaffleEndInPast
+			);
+			ensure!(
+				max_tickets >= T::MinRaffleTickets::get().max(1) &&
+					max_tickets <= T::MaxRaffleTickets::get(),
+				Error::<T>::InvalidRaffleTickets
+			);
+
+			let raffle_id = NextRaffleId::<T>::try_mutate(|id| -> Result<RaffleId, DispatchError> {
+				let current_id = *id;
+				*id = id.checked_add(1).ok_or(Error::<T>::RaffleIdOverflow)?;
+				Ok(current_id)
+			})?;
+			RafflesEnding::<T>::try_mutate(&end_block, |ids| ids.try_push(raffle_id))
+				.map_err(|_| Error::<T>::TooManyRafflesEnding)?;
+
+			// A raffled kitty is no longer for sale.
+			Kitties::<T>::mutate(&kitty_id, |maybe| {
+				if let Some(k) = maybe {
+					Self::set_price(&kitty_id, k, None);
+				}
+			});
+			KittyRaffle::<T>::insert(&kitty_id, raffle_id);
+			Raffles::<T>::insert(
+				raffle_id,
+				Raffle::<T> {
+					kitty_id,
+					owner: sender.clone(),
+					ticket_price,
+					max_tickets,
+					end_block,
+					tickets: Default::default(),
+				},
+			);
+
+			Self::deposit_event(Event::RaffleStarted(sender, raffle_id, kitty_id));
+			Ok(())
+		}
+
+		/// Buy a ticket of a raffle, the ticket price stays reserved until the raffle ends.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn buy_raffle_ticket(origin: OriginFor<T>, raffle_id: RaffleId) -> DispatchResult {
+			let buyer = ensure_signed(origin)?;
+			Self::ensure_not_blacklisted(&buyer)?;
+
+			Raffles::<T>::try_mutate(raffle_id, |maybe| -> DispatchResult {
+				let raffle = maybe.as_mut().ok_or(Error::<T>::RaffleNotExist)?;
+				ensure!(buyer != raffle.owner, Error::<T>::BuyerIsKittyOwner);
+				ensure!(
+					raffle.end_block > <frame_system::Pallet<T>>::block_number(),
+					Error::<T>::RaffleEnded
+				);
+				ensure!(
+					(raffle.tickets.len() as u32) < raffle.max_tickets,
+					Error::<T>::RaffleSoldOut
+				);
+
+				T::Currency::reserve(&buyer, raffle.ticket_price)?;
+				raffle.tickets.try_push(buyer.clone()).map_err(|_| Error::<T>::RaffleSoldOut)?;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::RaffleTicketBought(buyer, raffle_id));
+			Ok(())
+		}
+
+		/// Challenge the Kitty of another owner to a battle.
+		///
+		/// The battle is fought once the owner of `opponent` accepts. `ChallengeDeposit` is
+		/// reserved until then, and refunded if either Kitty changes hands or is burned first.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn challenge(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			opponent: T::KittyIndex,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+			ensure!(!Self::is_kitty_owner(&opponent, &sender)?, Error::<T>::CannotBattleOwnKitty);
+			Self::ensure_kitty_unlocked(&kitty_id)?;
+			Self::ensure_kitty_unlocked(&opponent)?;
+			ensure!(
+				!Challenges::<T>::contains_key(&opponent, &kitty_id),
+				Error::<T>::ChallengeExists
+			);
+
+			ChallengesIssued::<T>::try_mutate(&kitty_id, |ids| ids.try_push(opponent))
+				.map_err(|_| Error::<T>::TooManyChallenges)?;
+			ChallengesReceived::<T>::try_mutate(&opponent, |ids| ids.try_push(kitty_id))
+				.map_err(|_| Error::<T>::TooManyChallenges)?;
+			let deposit = T::ChallengeDeposit::get();
+			T::Currency::reserve(&sender, deposit)?;
+			Challenges::<T>::insert(&opponent, &kitty_id, (&sender, deposit));
+
+			Self::deposit_event(Event::ChallengeIssued(sender, kitty_id, opponent));
+			Ok(())
+		}
+
+		/// Accept the challenge of `challenger` and fight the battle.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn accept_challenge(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			challenger: T::KittyIndex,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+			Self::ensure_kitty_unlocked(&kitty_id)?;
+			Self::ensure_kitty_unlocked(&challenger)?;
+
+			let (issuer, _) =
+				Self::challenges(&kitty_id, &challenger).ok_or(Error::<T>::ChallengeNotExist)?;
+			// The challenge lapses once the challenging kitty changed hands.
+			ensure!(Self::is_kitty_owner(&challenger, &issuer)?, Error::<T>::ChallengeNotExist);
+			ensure!(issuer != sender, Error::<T>::CannotBattleOwnKitty);
+
+			Self::remove_challenge(&kitty_id, &challenger);
+			Self::battle(&challenger, &kitty_id)?;
+			Ok(())
+		}
+
+		/// Withdraw the challenge of `kitty_id` against `opponent`, refunding its deposit.
+		#[pallet::weight(100)]
+		pub fn withdraw_challenge(
+			origin: OriginFor<T>,
+			kitty_id: T::KittyIndex,
+			opponent: T::KittyIndex,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			let (issuer, _) =
+				Self::challenges(&opponent, &kitty_id).ok_or(Error::<T>::ChallengeNotExist)?;
+			ensure!(issuer == sender, Error::<T>::NotKittyOwner);
+
+			Self::remove_challenge(&opponent, &kitty_id);
+
+			Self::deposit_event(Event::ChallengeWithdrawn(sender, kitty_id, opponent));
+			Ok(())
+		}
+
+		/// Checkpoint the experience of a Kitty, emitting `LevelUp` when it reached a new level.
+		///
+		/// Anyone may call this, the experience itself accrues lazily.
+		#[pallet::weight(100)]
+		pub fn refresh_experience(origin: OriginFor<T>, kitty_id: T::KittyIndex) -> DispatchResult {
+			ensure_signed(origin)?;
+			ensure!(Kitties::<T>::contains_key(&kitty_id), Error::<T>::KittyNotExist);
+
+			Self::checkpoint_experience(&kitty_id);
+			Ok(())
+		}
+
+		/// Schedule a single-elimination tournament starting at `start_block`.
+		///
+		/// The winner's owner receives every entry fee.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn create_tournament(
+			origin: OriginFor<T>,
+			entry_fee: BalanceOf<T>,
+			start_block: T::BlockNumber,
+		) -> DispatchResult {
+			T::TournamentOrigin::ensure_origin(origin)?;
+			ensure!(
+				start_block > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::TournamentStartInPast
+			);
+			ensure!(
+				!TournamentsStarting::<T>::contains_key(&start_block),
+				Error::<T>::TournamentSlotTaken
+			);
+
+			let tournament_id =
+				NextTournamentId::<T>::try_mutate(|id| -> Result<TournamentId, DispatchError> {
+					let current_id = *id;
+					*id = id.checked_add(1).ok_or(Error::<T>::TournamentIdOverflow)?;
+					Ok(current_id)
+				})?;
+			TournamentsStarting::<T>::insert(&start_block, tournament_id);
+			Tournaments::<T>::insert(
+				tournament_id,
+				Tournament::<T> { entry_fee, start_block, entrants: Default::default() },
+			);
+
+			Self::deposit_event(Event::TournamentCreated(tournament_id, entry_fee, start_block));
+			Ok(())
+		}
+
+		/// Enter a Kitty in a tournament, reserving the entry fee.
+		///
+		/// The Kitty is locked until the tournament ran.
+		#[transactional]
+		#[pallet::weight(100)]
+		pub fn enter_tournament(
+			origin: OriginFor<T>,
+			tournament_id: TournamentId,
+			kitty_id: T::KittyIndex,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(Self::is_kitty_owner(&kitty_id, &sender)?, Error::<T>::NotKittyOwner);
+			Self::ensure_kitty_unlocked(&kitty_id)?;
+
+			Tournaments::<T>::try_mutate(tournament_id, |maybe| -> DispatchResult {
+				let tournament = maybe.as_mut().ok_or(Error::<T>::TournamentNotExist)?;
+				ensure!(
+					tournament.start_block > <frame_system::Pallet<T>>::block_number(),
+					Error::<T>::TournamentStarted
+				);
+
+				T::Currency::reserve(&sender, tournament.entry_fee)?;
+				tournament
+					.entrants
+					.try_push((kitty_id, sender.clone()))
+					.map_err(|_| Error::<T>::TournamentFull)?;
+				Ok(())
+			})?;
+
+			// An entered kitty is no longer for sale.
+			Kitties::<T>::mutate(&kitty_id, |maybe| {
+				if let Some(k) = maybe {
+					Self::set_price(&kitty_id, k, None);
+				}
+			});
+			KittyTournament::<T>::insert(&kitty_id, tournament_id);
+
+			Self::deposit_event(Event::TournamentEntered(sender, tournament_id, kitty_id));
+			Ok(())
+		}
+	}
+
+	//** Our helper functions.**//
+
+	impl<T: Config> Pallet<T> {
+		fn gen_gender() -> Gender {
+			let random = T::KittyRandomness::random(&b"gender"[..]).0;
+			match random.as_ref()[0] % 2 {
+				0 => Gender::Male,
 				_ => Gender::Female,
 			}
 		}
@@ -295,7 +1687,7 @@ pub mod pallet {
 			dna: Option<[u8; 16]>,
 			gender: Option<Gender>,
 		) -> Result<T::KittyIndex, DispatchError> {
-			let deposit = T::Pledge::get();
+			let deposit = Self::pledge();
 			T::Currency::reserve(&owner, deposit)?;
 
 			let kitty = Kitty::<T> {
@@ -314,13 +1706,801 @@ pub mod pallet {
 					Ok(current_id)
 				})?;
 
+			Self::add_owned_kitty(owner, &kitty_id)?;
 			<KittiesOwned<T>>::insert(kitty_id, owner);
+			KittyExperience::<T>::insert(
+				kitty_id,
+				Experience {
+					xp: 0,
+					level: 0,
+					last_update: <frame_system::Pallet<T>>::block_number(),
+				},
+			);
 
 			Kitties::<T>::insert(kitty_id, kitty);
 
+			T::OnKittyMinted::on_kitty_minted(owner, &kitty_id);
 			Ok(kitty_id)
 		}
 
+		/// The account holding the kitties in the adoption pool.
+		pub fn adoption_pool_account() -> T::AccountId {
+			T::AdoptionPoolId::get().into_account()
+		}
+
+		/// Moves the kitties of reaped accounts to the adoption pool, as far as
+		/// `remaining_weight` allows.
+		fn orphan_reaped_kitties(remaining_weight: Weight) -> Weight {
+			let db = T::DbWeight::get();
+			let per_account = db.reads_writes(2, 2);
+			// Orphaning a kitty also clears up to `2 * MaxChallengesPerKitty` challenges, its gift,
+			// and refunds its raffle tickets or tournament entry.
+			let challenges = 2 * T::MaxChallengesPerKitty::get() as Weight;
+			let tickets = T::MaxRaffleTickets::get() as Weight;
+			let per_kitty =
+				db.reads_writes(14 + 3 * challenges + tickets, 17 + 3 * challenges + tickets);
+			let mut used = db.reads(1);
+			if used > remaining_weight {
+				return 0
+			}
+
+			while used.saturating_add(per_account) <= remaining_weight {
+				let who = match ReapedOwners::<T>::iter_keys().next() {
+					Some(who) => who,
+					None => break,
+				};
+				used = used.saturating_add(per_account);
+
+				let mut owned = OwnedKitties::<T>::take(&who).into_inner();
+				while used.saturating_add(per_kitty) <= remaining_weight {
+					match owned.pop() {
+						Some(kitty_id) => Self::orphan_kitty(&who, &kitty_id),
+						None => break,
+					}
+					used = used.saturating_add(per_kitty);
+				}
+
+				if !owned.is_empty() {
+					// The rest is moved in a later block.
+					let rest: BoundedVec<_, T::MaxKittyOwned> =
+						owned.try_into().unwrap_or_default();
+					OwnedKitties::<T>::insert(&who, rest);
+					break
+				}
+				ReapedOwners::<T>::remove(&who);
+			}
+			used
+		}
+
+		/// Moves a Kitty of a reaped account to the adoption pool, its deposit went with the
+		/// account. Its gift, raffle and tournament entry are cancelled.
+		fn orphan_kitty(old_owner: &T::AccountId, kitty_id: &T::KittyIndex) {
+			let pool = Self::adoption_pool_account();
+			Self::checkpoint_experience(kitty_id);
+			Kitties::<T>::mutate(kitty_id, |maybe| {
+				if let Some(k) = maybe {
+					Self::set_price(kitty_id, k, None);
+					k.owner = pool.clone();
+					k.deposit = Zero::zero();
+				}
+			});
+			KittiesOwned::<T>::insert(kitty_id, &pool);
+			AdoptionPool::<T>::insert(kitty_id, ());
+			Self::clear_challenges(kitty_id);
+			Self::cancel_gift(kitty_id);
+			Self::cancel_raffle(kitty_id);
+			Self::withdraw_from_tournament(kitty_id);
+			Self::deposit_event(Event::KittyOrphaned(kitty_id.to_owned(), old_owner.clone()));
+		}
+
+		/// Fills `OwnedKitties` from `Kitties` on chains which ran before the index existed.
+		fn migrate_owned_kitties() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() >= STORAGE_VERSION {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let mut count: Weight = 0;
+			for (kitty_id, kitty) in Kitties::<T>::iter() {
+				count = count.saturating_add(1);
+				if Self::add_owned_kitty(&kitty.owner, &kitty_id).is_err() {
+					log::warn!("🐈 {:?} owns too many kitties to index.", kitty.owner);
+				}
+			}
+			STORAGE_VERSION.put::<Pallet<T>>();
+
+			log::info!("🐈 Indexed {} kitties in OwnedKitties.", count);
+			T::DbWeight::get().reads_writes(1 + 2 * count, 1 + count)
+		}
+
+		fn add_owned_kitty(owner: &T::AccountId, kitty_id: &T::KittyIndex) -> DispatchResult {
+			OwnedKitties::<T>::try_mutate(owner, |ids| {
+				ids.try_push(kitty_id.to_owned()).map_err(|_| Error::<T>::ExceedMaxKittyOwned)
+			})?;
+			Ok(())
+		}
+
+		fn remove_owned_kitty(owner: &T::AccountId, kitty_id: &T::KittyIndex) {
+			OwnedKitties::<T>::mutate(owner, |ids| {
+				if let Some(pos) = ids.iter().position(|id| id == kitty_id) {
+					ids.swap_remove(pos);
+				}
+			});
+		}
+
+		/// Ensures the Kitty may be traded, transferred or bred.
+		pub fn ensure_kitty_unlocked(kitty_id: &T::KittyIndex) -> DispatchResult {
+			ensure!(!FrozenKitties::<T>::contains_key(kitty_id), Error::<T>::KittyFrozen);
+			Self::ensure_kitty_idle(kitty_id)
+		}
+
+		/// Ensures the Kitty is not orphaned, raffled, entered in a tournament or gifted.
+		pub fn ensure_kitty_idle(kitty_id: &T::KittyIndex) -> DispatchResult {
+			ensure!(!AdoptionPool::<T>::contains_key(kitty_id), Error::<T>::KittyOrphaned);
+			ensure!(!KittyRaffle::<T>::contains_key(kitty_id), Error::<T>::KittyInRaffle);
+			ensure!(!KittyTournament::<T>::contains_key(kitty_id), Error::<T>::KittyInTournament);
+			ensure!(!KittyGift::<T>::contains_key(kitty_id), Error::<T>::KittyInGift);
+			Ok(())
+		}
+
+		/// The battle stats of a Kitty, boosted by its level.
+		pub fn kitty_stats(kitty_id: &T::KittyIndex) -> Result<KittyStats, Error<T>> {
+			let kitty = Self::kitties(kitty_id).ok_or(Error::<T>::KittyNotExist)?;
+			Ok(KittyStats::from_dna(&kitty.dna).boosted(Self::level(kitty_id)))
+		}
+
+		/// The experience of a Kitty up to the current block.
+		///
+		/// Experience does not accrue while the Kitty waits in the adoption pool.
+		pub fn experience(kitty_id: &T::KittyIndex) -> u64 {
+			let exp = Self::kitty_experience(kitty_id);
+			if AdoptionPool::<T>::contains_key(kitty_id) {
+				return exp.xp
+			}
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let blocks: u64 = now.saturating_sub(exp.last_update).unique_saturated_into();
+			exp.xp.saturating_add(blocks.saturating_mul(T::ExperiencePerBlock::get()))
+		}
+
+		/// The level of a Kitty up to the current block.
+		pub fn level(kitty_id: &T::KittyIndex) -> u32 {
+			Self::level_for(Self::experience(kitty_id))
+		}
+
+		fn level_for(xp: u64) -> u32 {
+			T::LevelThresholds::get().iter().filter(|threshold| xp >= **threshold).count() as u32
+		}
+
+		pub(super) fn checkpoint_experience(kitty_id: &T::KittyIndex) {
+			let xp = Self::experience(kitty_id);
+			let level = Self::level_for(xp);
+			KittyExperience::<T>::mutate(kitty_id, |exp| {
+				if level > exp.level {
+					Self::deposit_event(Event::LevelUp(kitty_id.to_owned(), level));
+				}
+				exp.xp = xp;
+				exp.level = level;
+				exp.last_update = <frame_system::Pallet<T>>::block_number();
+			});
+		}
+
+		/// Whether the breeding cooldown of a Kitty, shortened by its level, has passed.
+		fn can_breed(kitty_id: &T::KittyIndex) -> bool {
+			let last_bred = match Self::last_bred(kitty_id) {
+				Some(block) => block,
+				None => return true,
+			};
+			let percent = 100 - (Self::level(kitty_id).saturating_mul(10)).min(50);
+			let cooldown = T::BreedCooldown::get().saturating_mul(percent.into()) / 100u32.into();
+			<frame_system::Pallet<T>>::block_number() >= last_bred.saturating_add(cooldown)
+		}
+
+		/// Fights a battle and updates the records and ratings. Returns the winner.
+		///
+		/// Each side scores twice its attack plus its speed and a random roll, minus the
+		/// defense of the other side.
+		pub fn battle(
+			challenger: &T::KittyIndex,
+			defender: &T::KittyIndex,
+		) -> Result<T::KittyIndex, DispatchError> {
+			let stats_c = Self::kitty_stats(challenger)?;
+			let stats_d = Self::kitty_stats(defender)?;
+
+			let nonce = BattleNonce::<T>::mutate(|n| {
+				*n = n.wrapping_add(1);
+				*n
+			});
+			let seed =
+				T::KittyRandomness::random(&(&b"battle"[..], challenger, defender, nonce).encode())
+					.0;
+			let roll = seed.as_ref();
+
+			let score = |s: &KittyStats, other: &KittyStats, r: u8| {
+				(2 * s.attack as u32 + s.speed as u32 + r as u32)
+					.saturating_sub(other.defense as u32)
+			};
+			let score_c = score(&stats_c, &stats_d, roll[0]);
+			let score_d = score(&stats_d, &stats_c, roll[1]);
+			let challenger_won = score_c > score_d || (score_c == score_d && roll[2] % 2 == 0);
+
+			let (winner, loser) =
+				if challenger_won { (challenger, defender) } else { (defender, challenger) };
+			Self::record_battle(winner, loser);
+
+			Self::deposit_event(Event::BattleFought(
+				challenger.to_owned(),
+				defender.to_owned(),
+				winner.to_owned(),
+			));
+			Ok(winner.to_owned())
+		}
+
+		/// Updates the win/loss records and the Elo ratings after a battle.
+		///
+		/// The expected score uses a linear approximation of the Elo curve, clamped to
+		/// \[5%, 95%\].
+		fn record_battle(winner: &T::KittyIndex, loser: &T::KittyIndex) {
+			let mut w = Self::battle_records(winner);
+			let mut l = Self::battle_records(loser);
+
+			let diff = w.rating as i64 - l.rating as i64;
+			let expected_permill = (500 + diff * 1000 / 800).max(50).min(950) as u32;
+			let delta = ELO_K_FACTOR * (1000 - expected_permill) / 1000;
+
+			w.wins = w.wins.saturating_add(1);
+			w.rating = w.rating.saturating_add(delta);
+			l.losses = l.losses.saturating_add(1);
+			l.rating = l.rating.saturating_sub(delta);
+
+			BattleRecords::<T>::insert(winner, w);
+			BattleRecords::<T>::insert(loser, l);
+		}
+
+		/// Runs the bracket of a tournament and pays out the prize pool, or refunds the entry
+		/// fees when fewer than two kitties entered. Returns the number of entrants.
+		fn run_tournament(tournament_id: TournamentId) -> u64 {
+			let tournament = match Tournaments::<T>::take(tournament_id) {
+				Some(tournament) => tournament,
+				None => return 0,
+			};
+			for (kitty_id, _) in tournament.entrants.iter() {
+				KittyTournament::<T>::remove(kitty_id);
+			}
+
+			let won = tournament.entrants.len() >= 2 &&
+				Self::play_tournament(tournament_id, &tournament).is_ok();
+			if !won {
+				for (_, owner) in tournament.entrants.iter() {
+					T::Currency::unreserve(owner, tournament.entry_fee);
+				}
+				Self::deposit_event(Event::TournamentCancelled(tournament_id));
+			}
+			tournament.entrants.len() as u64
+		}
+
+		#[transactional]
+		fn play_tournament(
+			tournament_id: TournamentId,
+			tournament: &Tournament<T>,
+		) -> DispatchResult {
+			// Pair off neighbours each round, an unpaired kitty advances without a battle.
+			let mut round: Vec<T::KittyIndex> =
+				tournament.entrants.iter().map(|(kitty_id, _)| kitty_id.to_owned()).collect();
+			while round.len() > 1 {
+				let mut next = Vec::with_capacity((round.len() + 1) / 2);
+				for pair in round.chunks(2) {
+					match pair {
+						[challenger, defender] => next.push(Self::battle(challenger, defender)?),
+						[bye] => next.push(bye.to_owned()),
+						_ => {},
+					}
+				}
+				round = next;
+			}
+
+			let champion = round[0];
+			let (_, winner) = tournament
+				.entrants
+				.iter()
+				.find(|(kitty_id, _)| kitty_id == &champion)
+				.ok_or(Error::<T>::KittyNotExist)?
+				.clone();
+
+			let mut prize: BalanceOf<T> = Zero::zero();
+			for (_, owner) in tournament.entrants.iter() {
+				T::Currency::repatriate_reserved(
+					owner,
+					&winner,
+					tournament.entry_fee,
+					BalanceStatus::Free,
+				)?;
+				prize = prize.saturating_add(tournament.entry_fee);
+			}
+
+			Self::deposit_event(Event::TournamentWon(tournament_id, champion, winner, prize));
+			Ok(())
+		}
+
+		/// Moves the Kitty and its pledge to `to`, marking it not for sale.
+		///
+		/// Fails if the `OnKittyTransferred` handler vetoes the transfer.
+		fn do_transfer(
+			kitty_id: &T::KittyIndex,
+			k: &mut Kitty<T>,
+			to: &T::AccountId,
+		) -> DispatchResult {
+			let from = k.owner.clone();
+			T::OnKittyTransferred::on_kitty_transferred(&from, to, kitty_id)?;
+			T::Currency::repatriate_reserved(&from, to, k.deposit, BalanceStatus::Reserved)?;
+
+			k.owner = to.clone();
+			Self::set_price(kitty_id, k, None);
+			Self::reconcile_deposit(kitty_id, k)?;
+
+			Self::remove_owned_kitty(&from, kitty_id);
+			Self::add_owned_kitty(to, kitty_id)?;
+			KittiesOwned::<T>::insert(kitty_id, to);
+			Self::clear_challenges(kitty_id);
+			Ok(())
+		}
+
+		/// Removes the challenge of `challenger` against `opponent`, refunding its deposit.
+		fn remove_challenge(opponent: &T::KittyIndex, challenger: &T::KittyIndex) {
+			if let Some((issuer, deposit)) = Challenges::<T>::take(opponent, challenger) {
+				T::Currency::unreserve(&issuer, deposit);
+			}
+			ChallengesIssued::<T>::mutate(challenger, |ids| {
+				if let Some(pos) = ids.iter().position(|id| id == opponent) {
+					ids.swap_remove(pos);
+				}
+			});
+			ChallengesReceived::<T>::mutate(opponent, |ids| {
+				if let Some(pos) = ids.iter().position(|id| id == challenger) {
+					ids.swap_remove(pos);
+				}
+			});
+		}
+
+		/// Removes the open challenges issued and received by a Kitty, refunding their
+		/// deposits.
+		fn clear_challenges(kitty_id: &T::KittyIndex) {
+			for opponent in Self::challenges_issued(kitty_id).into_iter() {
+				Self::remove_challenge(&opponent, kitty_id);
+			}
+			for challenger in Self::challenges_received(kitty_id).into_iter() {
+				Self::remove_challenge(kitty_id, &challenger);
+			}
+		}
+
+		/// Sets the price of a Kitty and bumps its listing nonce. Any expiry of the previous
+		/// listing is dropped.
+		pub(super) fn set_price(
+			kitty_id: &T::KittyIndex,
+			k: &mut Kitty<T>,
+			price: Option<BalanceOf<T>>,
+		) {
+			k.price = price;
+			ListingNonce::<T>::mutate(kitty_id, |nonce| *nonce = nonce.wrapping_add(1));
+			if let Some(expiry) = ListingExpiry::<T>::take(kitty_id) {
+				Self::remove_expiring_listing(kitty_id, expiry);
+			}
+		}
+
+		/// Frees the slot of a Kitty among the listings expiring at `expiry`.
+		fn remove_expiring_listing(kitty_id: &T::KittyIndex, expiry: T::BlockNumber) {
+			ExpiringListings::<T>::mutate_exists(expiry, |maybe| {
+				if let Some(ids) = maybe {
+					if let Some(pos) = ids.iter().position(|id| id == kitty_id) {
+						ids.swap_remove(pos);
+					}
+					if ids.is_empty() {
+						*maybe = None;
+					}
+				}
+			});
+		}
+
+		/// The asking price of a Kitty, `None` once its listing expired.
+		pub fn active_price(kitty_id: &T::KittyIndex, k: &Kitty<T>) -> Option<BalanceOf<T>> {
+			match Self::listing_expiry(kitty_id) {
+				Some(expiry) if expiry <= <frame_system::Pallet<T>>::block_number() => None,
+				_ => k.price,
+			}
+		}
+
+		/// The message the owner signs to permit a transfer, bound to this chain's genesis.
+		pub fn permit_payload(
+			from: &T::AccountId,
+			to: &T::AccountId,
+			kitty_id: &T::KittyIndex,
+			deadline: T::BlockNumber,
+			nonce: u32,
+		) -> Vec<u8> {
+			let genesis = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+			(b"kitty-permit", genesis, from, to, kitty_id, deadline, nonce).encode()
+		}
+
+		/// Checks the deadline, nonce and signature of a transfer permit.
+		fn check_permit(
+			from: &T::AccountId,
+			to: &T::AccountId,
+			kitty_id: &T::KittyIndex,
+			deadline: T::BlockNumber,
+			nonce: u32,
+			signature: &T::PermitSignature,
+		) -> Result<(), Error<T>> {
+			ensure!(
+				deadline >= <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::PermitExpired
+			);
+			ensure!(nonce == Self::permit_nonce(from), Error::<T>::InvalidPermitNonce);
+			let payload = Self::permit_payload(from, to, kitty_id, deadline, nonce);
+			ensure!(signature.verify(&payload[..], from), Error::<T>::InvalidPermitSignature);
+			Ok(())
+		}
+
+		/// The message the gift key signs to send the gifted Kitty to `recipient`, bound to this
+		/// chain's genesis.
+		pub fn gift_claim_payload(
+			gift_key: &T::AccountId,
+			kitty_id: &T::KittyIndex,
+			recipient: &T::AccountId,
+			expiry: T::BlockNumber,
+		) -> Vec<u8> {
+			let genesis = <frame_system::Pallet<T>>::block_hash(T::BlockNumber::zero());
+			(b"kitty-gift", genesis, gift_key, kitty_id, recipient, expiry).encode()
+		}
+
+		/// The pending gift under `gift_key`, if it did not expire yet and the claim of
+		/// `recipient` is signed by the gift key.
+		fn check_gift(
+			gift_key: &T::AccountId,
+			recipient: &T::AccountId,
+			signature: &T::PermitSignature,
+		) -> Result<Gift<T>, Error<T>> {
+			let gift = Self::gifts(gift_key).ok_or(Error::<T>::GiftNotExist)?;
+			ensure!(
+				gift.expiry > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::GiftNotExist
+			);
+			let payload =
+				Self::gift_claim_payload(gift_key, &gift.kitty_id, recipient, gift.expiry);
+			ensure!(signature.verify(&payload[..], gift_key), Error::<T>::InvalidGiftSignature);
+			Ok(gift)
+		}
+
+		/// Checks that the current block can take another gift claim.
+		fn ensure_gift_claims_available() -> Result<(), Error<T>> {
+			let (block, count) = GiftClaims::<T>::get();
+			ensure!(
+				block != <frame_system::Pallet<T>>::block_number() ||
+					count < T::MaxGiftClaimsPerBlock::get(),
+				Error::<T>::TooManyGiftClaims
+			);
+			Ok(())
+		}
+
+		/// Releases the Kitty of a gift left unclaimed at block `n` back to its sender.
+		fn expire_gift(gift_key: &T::AccountId, n: T::BlockNumber) {
+			// Skip a key reused by a gift created since.
+			match Self::gifts(gift_key) {
+				Some(gift) if gift.expiry == n => {
+					Gifts::<T>::remove(gift_key);
+					KittyGift::<T>::remove(&gift.kitty_id);
+					Self::deposit_event(Event::GiftExpired(gift.sender, gift.kitty_id));
+				},
+				_ => {},
+			}
+		}
+
+		/// Cancels the pending gift of a Kitty, if any.
+		fn cancel_gift(kitty_id: &T::KittyIndex) {
+			if let Some(gift_key) = KittyGift::<T>::take(kitty_id) {
+				if let Some(gift) = Gifts::<T>::take(&gift_key) {
+					Self::remove_expiring_gift(&gift_key, gift.expiry);
+					Self::deposit_event(Event::GiftCancelled(gift.sender, gift.kitty_id));
+				}
+			}
+		}
+
+		/// Cancels the running raffle of a Kitty, if any, refunding its tickets.
+		fn cancel_raffle(kitty_id: &T::KittyIndex) {
+			if let Some(raffle_id) = KittyRaffle::<T>::take(kitty_id) {
+				if let Some(raffle) = Raffles::<T>::take(raffle_id) {
+					RafflesEnding::<T>::mutate_exists(raffle.end_block, |maybe| {
+						if let Some(ids) = maybe {
+							if let Some(pos) = ids.iter().position(|id| *id == raffle_id) {
+								ids.swap_remove(pos);
+							}
+							if ids.is_empty() {
+								*maybe = None;
+							}
+						}
+					});
+					for buyer in raffle.tickets.iter() {
+						T::Currency::unreserve(buyer, raffle.ticket_price);
+					}
+					Self::deposit_event(Event::RaffleCancelled(raffle_id));
+				}
+			}
+		}
+
+		/// Takes a Kitty out of the tournament it entered, if any, refunding its entry fee.
+		fn withdraw_from_tournament(kitty_id: &T::KittyIndex) {
+			if let Some(tournament_id) = KittyTournament::<T>::take(kitty_id) {
+				Tournaments::<T>::mutate(tournament_id, |maybe| {
+					if let Some(tournament) = maybe {
+						let entrants = &mut tournament.entrants;
+						if let Some(pos) = entrants.iter().position(|(id, _)| id == kitty_id) {
+							let (_, owner) = entrants.remove(pos);
+							T::Currency::unreserve(&owner, tournament.entry_fee);
+						}
+					}
+				});
+			}
+		}
+
+		/// Frees the slot of a gift among the gifts expiring at `expiry`.
+		fn remove_expiring_gift(gift_key: &T::AccountId, expiry: T::BlockNumber) {
+			GiftsExpiring::<T>::mutate_exists(expiry, |maybe| {
+				if let Some(keys) = maybe {
+					if let Some(pos) = keys.iter().position(|key| key == gift_key) {
+						keys.swap_remove(pos);
+					}
+					if keys.is_empty() {
+						*maybe = None;
+					}
+				}
+			});
+		}
+
+		/// Checks the invariants of the pallet storage.
+		///
+		/// Run by try-runtime after a runtime upgrade, and by the tests after every test and block.
+		#[cfg(any(feature = "try-runtime", test))]
+		pub fn do_try_state() -> Result<(), &'static str> {
+			use sp_std::collections::btree_map::BTreeMap;
+
+			let mut count: u64 = 0;
+			let mut deposits = BTreeMap::<T::AccountId, BalanceOf<T>>::new();
+			for (kitty_id, kitty) in Kitties::<T>::iter() {
+				count = count.saturating_add(1);
+				ensure!(
+					Self::kitties_owned(&kitty_id).as_ref() == Some(&kitty.owner),
+					"KittiesOwned does not match the owner of a Kitty"
+				);
+				let deposit = deposits.entry(kitty.owner.clone()).or_insert_with(Zero::zero);
+				*deposit = deposit.saturating_add(kitty.deposit);
+
+				if AdoptionPool::<T>::contains_key(&kitty_id) {
+					ensure!(
+						kitty.owner == Self::adoption_pool_account(),
+						"An orphaned Kitty is not held by the adoption pool"
+					);
+				}
+
+				let locked = FrozenKitties::<T>::contains_key(&kitty_id) ||
+					Self::ensure_kitty_idle(&kitty_id).is_err();
+				ensure!(!(locked && kitty.price.is_some()), "A frozen or locked Kitty has a price");
+			}
+
+			let cnt: u64 = Self::kitty_cnt().unique_saturated_into();
+			ensure!(cnt >= count, "KittyCnt is lower than the number of kitties");
+
+			for (opponent, challenger, _) in Challenges::<T>::iter() {
+				ensure!(
+					Self::challenges_issued(&challenger).contains(&opponent) &&
+						Self::challenges_received(&opponent).contains(&challenger),
+					"An open challenge is missing from the challenge indexes"
+				);
+			}
+
+			for (kitty_id, owner) in KittiesOwned::<T>::iter() {
+				let kitty = Self::kitties(&kitty_id).ok_or("KittiesOwned entry without a Kitty")?;
+				ensure!(kitty.owner == owner, "KittiesOwned does not match the owner of a Kitty");
+			}
+
+			// The kitties of reaped accounts lost their deposit until `on_idle` orphans them.
+			for (owner, deposit) in deposits {
+				if ReapedOwners::<T>::contains_key(&owner) {
+					continue
+				}
+				ensure!(
+					T::Currency::reserved_balance(&owner) >= deposit,
+					"Reserved balance does not cover the Kitty deposits"
+				);
+			}
+			Ok(())
+		}
+
+		/// Ensures the account is not blacklisted from trading.
+		pub fn ensure_not_blacklisted(who: &T::AccountId) -> DispatchResult {
+			ensure!(!Blacklist::<T>::contains_key(who), Error::<T>::AccountBlacklisted);
+			Ok(())
+		}
+
+		/// Checks that a batch has between one and `MaxKittyOwned` items, returning its size.
+		fn ensure_batch_size(len: usize) -> Result<u32, Error<T>> {
+			ensure!(len > 0, Error::<T>::EmptyBatch);
+			ensure!(len <= T::MaxKittyOwned::get() as usize, Error::<T>::BatchTooLarge);
+			Ok(len as u32)
+		}
+
+		/// Appends a sale to the histories, dropping the oldest entries once they are full.
+		fn record_sale(sale: Sale<T>) {
+			fn push_ring<V, S: Get<u32>>(ring: &mut BoundedVec<V, S>, value: V) {
+				if ring.len() as u32 >= S::get() {
+					if ring.is_empty() {
+						return
+					}
+					ring.remove(0);
+				}
+				let _ = ring.try_push(value);
+			}
+
+			KittySales::<T>::mutate(&sale.kitty_id, |sales| push_ring(sales, sale.clone()));
+			RecentTrades::<T>::mutate(|trades| push_ring(trades, sale));
+		}
+
+		/// Trade count, volume, floor and average price of the recent trades in the last
+		/// `window` blocks.
+		pub fn market_stats(window: T::BlockNumber) -> MarketStats<BalanceOf<T>> {
+			let since = <frame_system::Pallet<T>>::block_number().saturating_sub(window);
+
+			let mut stats = MarketStats::<BalanceOf<T>>::default();
+			for sale in Self::recent_trades().iter().filter(|sale| sale.block > since) {
+				stats.trades += 1;
+				stats.volume = stats.volume.saturating_add(sale.price);
+				stats.floor_price = Some(match stats.floor_price {
+					Some(floor) => floor.min(sale.price),
+					None => sale.price,
+				});
+			}
+			if stats.trades > 0 {
+				stats.average_price = Some(stats.volume / stats.trades.into());
+			}
+			stats
+		}
+
+		/// Clears expired listings block by block, as far as `remaining_weight` allows.
+		///
+		/// A block whose listings do not all fit is left with the rest for a later block.
+		fn purge_expired_listings(n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let db = T::DbWeight::get();
+			let per_block = db.reads_writes(1, 2);
+			let per_listing = db.reads_writes(4, 3);
+			let mut used = db.reads_writes(1, 1);
+			if used > remaining_weight {
+				return 0
+			}
+
+			let mut block = match Self::next_expiry_block() {
+				Some(block) => block,
+				None => return db.reads(1),
+			};
+			while block <= n && used.saturating_add(per_block) <= remaining_weight {
+				used = used.saturating_add(per_block);
+
+				let mut expiring = ExpiringListings::<T>::take(&block).into_inner();
+				while used.saturating_add(per_listing) <= remaining_weight {
+					match expiring.pop() {
+						Some(kitty_id) => Self::expire_listing(&kitty_id, block),
+						None => break,
+					}
+					used = used.saturating_add(per_listing);
+				}
+
+				if !expiring.is_empty() {
+					// The rest is purged in a later block.
+					let rest: BoundedVec<_, T::MaxListingsExpiringPerBlock> =
+						expiring.try_into().unwrap_or_default();
+					ExpiringListings::<T>::insert(&block, rest);
+					break
+				}
+				block = block.saturating_add(One::one());
+			}
+
+			NextExpiryBlock::<T>::put(block);
+			used
+		}
+
+		/// Delists a Kitty whose listing expired at `block`.
+		fn expire_listing(kitty_id: &T::KittyIndex, block: T::BlockNumber) {
+			// Skip listings which were repriced, delisted or transferred since.
+			if Self::listing_expiry(kitty_id) != Some(block) {
+				return
+			}
+			Kitties::<T>::mutate(kitty_id, |maybe| {
+				if let Some(k) = maybe {
+					Self::set_price(kitty_id, k, None);
+					Self::deposit_event(Event::PriceSet(
+						k.owner.clone(),
+						kitty_id.to_owned(),
+						None,
+						PriceChange::Expired,
+					));
+				}
+			});
+		}
+
+		/// Draws the winner of an ended raffle or refunds its tickets. Returns the number of
+		/// tickets sold.
+		fn settle_raffle(raffle_id: RaffleId) -> u64 {
+			let raffle = match Raffles::<T>::take(raffle_id) {
+				Some(raffle) => raffle,
+				None => return 0,
+			};
+			KittyRaffle::<T>::remove(&raffle.kitty_id);
+
+			let drawn = (raffle.tickets.len() as u32) >= T::MinRaffleTickets::get() &&
+				Self::draw_raffle(raffle_id, &raffle).is_ok();
+			if !drawn {
+				for buyer in raffle.tickets.iter() {
+					T::Currency::unreserve(buyer, raffle.ticket_price);
+				}
+				Self::deposit_event(Event::RaffleCancelled(raffle_id));
+			}
+			raffle.tickets.len() as u64
+		}
+
+		#[transactional]
+		fn draw_raffle(raffle_id: RaffleId, raffle: &Raffle<T>) -> DispatchResult {
+			ensure!(!raffle.tickets.is_empty(), Error::<T>::InvalidRaffleTickets);
+
+			let seed = T::KittyRandomness::random(&(&b"raffle"[..], raffle_id).encode()).0;
+			let random = u32::decode(&mut seed.as_ref()).unwrap_or_default();
+			let winner = raffle.tickets[(random as usize) % raffle.tickets.len()].clone();
+
+			Kitties::<T>::try_mutate(&raffle.kitty_id, |maybe| -> DispatchResult {
+				let k = maybe.as_mut().ok_or(Error::<T>::KittyNotExist)?;
+				ensure!(k.owner == raffle.owner, Error::<T>::NotKittyOwner);
+				Self::ensure_kitty_unlocked(&raffle.kitty_id)?;
+				Self::do_transfer(&raffle.kitty_id, k, &winner)
+			})?;
+
+			let mut proceeds: BalanceOf<T> = Zero::zero();
+			for buyer in raffle.tickets.iter() {
+				T::Currency::repatriate_reserved(
+					buyer,
+					&raffle.owner,
+					raffle.ticket_price,
+					BalanceStatus::Free,
+				)?;
+				proceeds = proceeds.saturating_add(raffle.ticket_price);
+			}
+
+			Self::deposit_event(Event::RaffleDrawn(
+				raffle_id,
+				winner,
+				raffle.kitty_id,
+				proceeds,
+			));
+			Ok(())
+		}
+
+		/// Reserve or unreserve funds of the kitty owner so its deposit matches the current
+		/// pledge. Returns whether the deposit changed.
+		fn reconcile_deposit(
+			kitty_id: &T::KittyIndex,
+			kitty: &mut Kitty<T>,
+		) -> Result<bool, DispatchError> {
+			let pledge = Self::pledge();
+			let old_deposit = kitty.deposit;
+			if old_deposit == pledge {
+				return Ok(false)
+			}
+
+			if pledge > old_deposit {
+				T::Currency::reserve(&kitty.owner, pledge.saturating_sub(old_deposit))?;
+			} else {
+				T::Currency::unreserve(&kitty.owner, old_deposit.saturating_sub(pledge));
+			}
+			kitty.deposit = pledge;
+
+			Self::deposit_event(Event::DepositRepriced(kitty_id.to_owned(), old_deposit, pledge));
+			Ok(true)
+		}
+
 		pub fn is_kitty_owner(
 			kitty_id: &T::KittyIndex,
 			acct: &T::AccountId,
@@ -341,23 +2521,19 @@ pub mod pallet {
 			Kitties::<T>::try_mutate(kitty_id, |maybe| {
 
 				let k = maybe.as_mut().ok_or(Error::<T>::KittyNotExist)?;
+				Self::ensure_kitty_unlocked(kitty_id)?;
+				Self::ensure_not_blacklisted(who)?;
+				Self::ensure_not_blacklisted(&k.owner)?;
+				if let Some(new_owner) = &to {
+					Self::ensure_not_blacklisted(new_owner)?;
+				}
 
 				if &k.owner == who {
 					if let Some(new_owner) = to {
 
 						ensure!(who != &new_owner, Error::<T>::TransferToSelf);
 
-						T::Currency::repatriate_reserved(
-							&k.owner,
-							&new_owner,
-							k.deposit,
-							BalanceStatus::Reserved,
-						)?;
-
-						k.owner = new_owner.clone();
-						k.price = None;
-
-						<KittiesOwned<T>>::insert(kitty_id, new_owner.clone());
+						Self::do_transfer(kitty_id, k, &new_owner)?;
 
 						Self::deposit_event(Event::Transferred(
 							who.to_owned(),
@@ -366,35 +2542,35 @@ pub mod pallet {
 						));
 
 						// just change the price if to is None
-					} else {  
+					} else {
 
-						k.price = price;
+						let change = match (Self::active_price(kitty_id, k), price) {
+							(_, None) => PriceChange::Delisted,
+							(None, Some(_)) => PriceChange::Listed,
+							(Some(_), Some(_)) => PriceChange::Repriced,
+						};
+						Self::set_price(kitty_id, k, price);
 						Self::deposit_event(Event::PriceSet(
 							who.to_owned(),
 							kitty_id.to_owned(),
 							price,
+							change,
 						));
 					}
 
 					Ok(())
 				} else {
 
-					let bid_price = price.ok_or(Error::<T>::NotKittyOwner)?;
-					if let Some(ask_price) = k.price {
-						ensure!(ask_price <= bid_price, Error::<T>::KittyBidPriceTooLow);
-					} else {
-						Err(Error::<T>::KittyNotForSale)?;
-					}
-
-					T::Currency::repatriate_reserved(
-						&k.owner,
-						who,
-						k.deposit,
-						BalanceStatus::Reserved,
-					)?;
+					let max_price = price.ok_or(Error::<T>::NotKittyOwner)?;
+					let ask_price =
+						Self::active_price(kitty_id, k).ok_or(Error::<T>::KittyNotForSale)?;
+					ensure!(ask_price <= max_price, Error::<T>::KittyBidPriceTooLow);
 
+					// The buyer pays the asking price and tops up the deposit if the pledge
+					// went up, check both before any funds move.
+					let top_up = Self::pledge().saturating_sub(k.deposit);
 					ensure!(
-						T::Currency::free_balance(who) >= bid_price,
+						T::Currency::free_balance(who) >= ask_price.saturating_add(top_up),
 						Error::<T>::NotEnoughBalance
 					);
 
@@ -403,20 +2579,25 @@ pub mod pallet {
 					T::Currency::transfer(
 						who,
 						&seller,
-						bid_price,
+						ask_price,
 						ExistenceRequirement::KeepAlive,
 					)?;
 
-					k.owner = who.to_owned();
-					k.price = None;
+					Self::do_transfer(kitty_id, k, who)?;
 
-					KittiesOwned::<T>::insert(kitty_id, who.to_owned());
+					Self::record_sale(Sale::<T> {
+						kitty_id: kitty_id.to_owned(),
+						price: ask_price,
+						block: <frame_system::Pallet<T>>::block_number(),
+						buyer: who.to_owned(),
+						seller: seller.clone(),
+					});
 
 					Self::deposit_event(Event::Bought(
 						who.to_owned(),
 						seller,
 						kitty_id.to_owned(),
-						bid_price,
+						ask_price,
 					));
 
 					Ok(())
@@ -424,4 +2605,14 @@ pub mod pallet {
 			})
 		}
 	}
-}
\ No newline at end of file
+}
+
+/// Kitties of a reaped account are moved to the adoption pool from `on_idle`, their deposit
+/// went with the account.
+impl<T: Config> OnKilledAccount<T::AccountId> for Pallet<T> {
+	fn on_killed_account(who: &T::AccountId) {
+		if OwnedKitties::<T>::decode_len(who).unwrap_or(0) > 0 {
+			ReapedOwners::<T>::insert(who, ());
+		}
+	}
+}
@@ -1,14 +1,18 @@
 #![cfg(test)]
 
 use crate as pallet_kitties;
-use frame_support::parameter_types;
-use pallet_kitties::Gender;
+use frame_support::{
+	dispatch::DispatchResult, parameter_types, traits::Hooks, weights::RuntimeDbWeight, PalletId,
+};
+use pallet_kitties::{Gender, OnKittyBred, OnKittyBurned, OnKittyMinted, OnKittyTransferred};
 use sp_core::H256;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
-	BuildStorage, AccountId32,
+	transaction_validity::TransactionPriority,
+	BuildStorage, AccountId32, DispatchError, MultiSignature, MultiSigner,
 };
+use std::cell::RefCell;
 
 
 pub type AccountId = AccountId32;
@@ -30,13 +34,15 @@ frame_support::construct_runtime!(
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
 		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
 		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Pallet, Storage},
-		Kitties: pallet_kitties::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Kitties: pallet_kitties::{Pallet, Call, Storage, Config<T>, Event<T>, ValidateUnsigned},
 	}
 );
 
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
+	// Unit weights, so the tests can run out of idle weight.
+	pub const TestDbWeight: RuntimeDbWeight = RuntimeDbWeight { read: 1, write: 1 };
 }
 
 impl frame_system::Config for Test {
@@ -48,14 +54,14 @@ impl frame_system::Config for Test {
 	type BlockNumber = u64;
 	type BlockWeights = ();
 	type Call = Call;
-	type DbWeight = ();
+	type DbWeight = TestDbWeight;
 	type Event = Event;
 	type Hash = H256;
 	type Hashing = BlakeTwo256;
 	type Header = Header;
 	type Index = u64;
 	type Lookup = IdentityLookup<Self::AccountId>;
-	type OnKilledAccount = ();
+	type OnKilledAccount = Kitties;
 	type OnNewAccount = ();
 	type OnSetCode = ();
 	type Origin = Origin;
@@ -85,7 +91,23 @@ parameter_types! {
 	// One can owned at most 9,999 Kitties
 	pub const MaxKittyOwned: u32 = 9999;
 	pub const Pledge: u64 = 5000;
-	
+	pub const AdoptionPoolId: PalletId = PalletId(*b"py/kpool");
+	pub const RepriceReward: u64 = 10;
+	pub const MinRaffleTickets: u32 = 2;
+	pub const MaxRaffleTickets: u32 = 10;
+	pub const MaxRafflesPerBlock: u32 = 5;
+	pub const ChallengeDeposit: u64 = 100;
+	pub const MaxChallengesPerKitty: u32 = 2;
+	pub const MaxTournamentEntrants: u32 = 8;
+	pub const ExperiencePerBlock: u64 = 1;
+	pub LevelThresholds: Vec<u64> = vec![10, 100, 1000];
+	pub const BreedCooldown: u64 = 20;
+	pub const MaxListingsExpiringPerBlock: u32 = 10;
+	pub const MaxSalesPerKitty: u32 = 2;
+	pub const MaxRecentTrades: u32 = 3;
+	pub const UnsignedPriority: TransactionPriority = 100;
+	pub const MaxGiftsExpiringPerBlock: u32 = 10;
+	pub const MaxGiftClaimsPerBlock: u32 = 1;
 }
 
 impl pallet_kitties::Config for Test {
@@ -94,37 +116,145 @@ impl pallet_kitties::Config for Test {
 	type KittyRandomness = RandomnessCollectiveFlip;
 	type KittyIndex = u64;
 	type Pledge = Pledge;
+	type PledgeOrigin = frame_system::EnsureRoot<AccountId>;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type AdoptionPoolId = AdoptionPoolId;
+	type RepriceReward = RepriceReward;
 	type MaxKittyOwned = MaxKittyOwned;
+	type MinRaffleTickets = MinRaffleTickets;
+	type MaxRaffleTickets = MaxRaffleTickets;
+	type MaxRafflesPerBlock = MaxRafflesPerBlock;
+	type ChallengeDeposit = ChallengeDeposit;
+	type MaxChallengesPerKitty = MaxChallengesPerKitty;
+	type TournamentOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxTournamentEntrants = MaxTournamentEntrants;
+	type ExperiencePerBlock = ExperiencePerBlock;
+	type LevelThresholds = LevelThresholds;
+	type BreedCooldown = BreedCooldown;
+	type MaxListingsExpiringPerBlock = MaxListingsExpiringPerBlock;
+	type MaxSalesPerKitty = MaxSalesPerKitty;
+	type MaxRecentTrades = MaxRecentTrades;
+	type PermitSignature = MultiSignature;
+	type PermitSigner = MultiSigner;
+	type UnsignedPriority = UnsignedPriority;
+	type MaxGiftsExpiringPerBlock = MaxGiftsExpiringPerBlock;
+	type MaxGiftClaimsPerBlock = MaxGiftClaimsPerBlock;
+	type OnKittyMinted = (KittyHooks, ());
+	type OnKittyTransferred = (KittyHooks, ());
+	type OnKittyBurned = KittyHooks;
+	type OnKittyBred = KittyHooks;
 }
 
 impl pallet_randomness_collective_flip::Config for Test {}
 
-pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
-	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
-	GenesisConfig {
-		balances: BalancesConfig { balances: vec![(ALICE, 10000), (BOB, 10000)] },
-		kitties: KittiesConfig {
-			kitties: vec![
-				(ALICE, *b"1234567890123456", Gender::Female),
-				(BOB, *b"123456789012345a", Gender::Male),
-			],
-		},
-		..Default::default()
+#[derive(Clone, Debug, PartialEq)]
+pub enum HookCall {
+	Minted(AccountId, u64),
+	Transferred(AccountId, AccountId, u64),
+	Burned(AccountId, u64),
+	Bred(AccountId, (u64, u64), u64),
+}
+
+thread_local! {
+	pub static HOOK_CALLS: RefCell<Vec<HookCall>> = RefCell::new(vec![]);
+	pub static VETO_TRANSFERS: RefCell<bool> = RefCell::new(false);
+}
+
+pub(crate) fn hook_calls() -> Vec<HookCall> {
+	HOOK_CALLS.with(|calls| calls.borrow().clone())
+}
+
+fn reset_hooks() {
+	HOOK_CALLS.with(|calls| calls.borrow_mut().clear());
+	VETO_TRANSFERS.with(|veto| *veto.borrow_mut() = false);
+}
+
+fn record_hook(call: HookCall) {
+	HOOK_CALLS.with(|calls| calls.borrow_mut().push(call));
+}
+
+/// Records every kitty hook call, and vetoes transfers while `VETO_TRANSFERS` is set.
+pub struct KittyHooks;
+
+impl OnKittyMinted<AccountId, u64> for KittyHooks {
+	fn on_kitty_minted(owner: &AccountId, kitty_id: &u64) {
+		record_hook(HookCall::Minted(owner.clone(), *kitty_id));
 	}
-	.assimilate_storage(&mut t)
-	.unwrap();
+}
 
-	let mut ext = sp_io::TestExternalities::new(t);
-	ext.execute_with(|| System::set_block_number(1));
-	ext
+impl OnKittyTransferred<AccountId, u64> for KittyHooks {
+	fn on_kitty_transferred(from: &AccountId, to: &AccountId, kitty_id: &u64) -> DispatchResult {
+		if VETO_TRANSFERS.with(|veto| *veto.borrow()) {
+			return Err(DispatchError::Other("transfer vetoed"))
+		}
+		record_hook(HookCall::Transferred(from.clone(), to.clone(), *kitty_id));
+		Ok(())
+	}
+}
+
+impl OnKittyBurned<AccountId, u64> for KittyHooks {
+	fn on_kitty_burned(owner: &AccountId, kitty_id: &u64) {
+		record_hook(HookCall::Burned(owner.clone(), *kitty_id));
+	}
+}
+
+impl OnKittyBred<AccountId, u64> for KittyHooks {
+	fn on_kitty_bred(owner: &AccountId, parents: (&u64, &u64), child: &u64) {
+		record_hook(HookCall::Bred(owner.clone(), (*parents.0, *parents.1), *child));
+	}
+}
+
+/// `frame_support::assert_ok!`, then checks the kitties invariants.
+macro_rules! assert_ok {
+	($($arg:tt)*) => {{
+		frame_support::assert_ok!($($arg)*);
+		$crate::mock::Kitties::do_try_state().expect("kitties invariants should hold");
+	}};
+}
+
+/// `frame_support::assert_noop!`, then checks the kitties invariants.
+macro_rules! assert_noop {
+	($($arg:tt)*) => {{
+		frame_support::assert_noop!($($arg)*);
+		$crate::mock::Kitties::do_try_state().expect("kitties invariants should hold");
+	}};
+}
+
+/// `frame_support::assert_err!`, then checks the kitties invariants.
+macro_rules! assert_err {
+	($($arg:tt)*) => {{
+		frame_support::assert_err!($($arg)*);
+		$crate::mock::Kitties::do_try_state().expect("kitties invariants should hold");
+	}};
 }
 
+pub(crate) use assert_err;
+pub(crate) use assert_noop;
+pub(crate) use assert_ok;
 
+/// Test externalities which check the kitties invariants once the test body ran.
+pub(crate) struct KittiesExt(pub(crate) sp_io::TestExternalities);
 
-pub(crate) fn new_test_ext_for_create() -> sp_io::TestExternalities {
+impl KittiesExt {
+	pub(crate) fn execute_with<R>(&mut self, execute: impl FnOnce() -> R) -> R {
+		self.0.execute_with(|| {
+			let result = execute();
+			Kitties::do_try_state().expect("kitties invariants should hold");
+			result
+		})
+	}
+}
+
+/// Test externalities with the given balances and genesis kitties, at block 1.
+pub(crate) fn new_test_ext_with(
+	balances: Vec<(AccountId, u64)>,
+	kitties: Vec<(AccountId, [u8; 16], Gender)>,
+) -> KittiesExt {
+	reset_hooks();
 	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 	GenesisConfig {
-		balances: BalancesConfig { balances: vec![(ALICE, 10000), (BOB, 10000)] },
+		balances: BalancesConfig { balances },
+		kitties: KittiesConfig { kitties },
 		..Default::default()
 	}
 	.assimilate_storage(&mut t)
@@ -132,5 +262,28 @@ pub(crate) fn new_test_ext_for_create() -> sp_io::TestExternalities {
 
 	let mut ext = sp_io::TestExternalities::new(t);
 	ext.execute_with(|| System::set_block_number(1));
-	ext
-}
\ No newline at end of file
+	KittiesExt(ext)
+}
+
+pub(crate) fn new_test_ext() -> KittiesExt {
+	new_test_ext_with(
+		vec![(ALICE, 10000), (BOB, 10000)],
+		vec![
+			(ALICE, *b"1234567890123456", Gender::Female),
+			(BOB, *b"123456789012345a", Gender::Male),
+		],
+	)
+}
+
+pub(crate) fn new_test_ext_for_create() -> KittiesExt {
+	new_test_ext_with(vec![(ALICE, 10000), (BOB, 10000)], vec![])
+}
+
+pub(crate) fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Kitties::on_initialize(System::block_number());
+		Kitties::on_idle(System::block_number(), u64::MAX);
+		Kitties::do_try_state().expect("kitties invariants should hold");
+	}
+}
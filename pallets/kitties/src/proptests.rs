@@ -0,0 +1,214 @@
+#![cfg(test)]
+
+//! Random sequences of kitty extrinsics checked against an in-memory model of ownership and
+//! balances.
+
+use crate::mock::*;
+use frame_support::assert_ok;
+use proptest::prelude::*;
+use std::collections::BTreeMap;
+
+const ACCOUNTS: [AccountId; 3] = [ALICE, BOB, COCO];
+const INITIAL_BALANCE: u64 = 100_000;
+
+#[derive(Clone, Debug)]
+enum Action {
+	Create(usize),
+	Sell(usize, u64, u64),
+	Buy(usize, u64, u64),
+	Transfer(usize, usize, u64),
+	Breed(usize, u64, u64),
+	NextBlock,
+}
+
+fn action() -> impl Strategy<Value = Action> {
+	// Kitty indexes go past the created kitties, so missing kitties are exercised too.
+	prop_oneof![
+		(0..3usize).prop_map(Action::Create),
+		(0..3usize, 0..8u64, 0..1000u64).prop_map(|(who, id, price)| Action::Sell(who, id, price)),
+		(0..3usize, 0..8u64, 0..1000u64).prop_map(|(who, id, max)| Action::Buy(who, id, max)),
+		(0..3usize, 0..3usize, 0..8u64).prop_map(|(from, to, id)| Action::Transfer(from, to, id)),
+		(0..3usize, 0..8u64, 0..8u64).prop_map(|(who, a, b)| Action::Breed(who, a, b)),
+		Just(Action::NextBlock),
+	]
+}
+
+/// What the chain should look like, updated only by extrinsics which succeeded.
+#[derive(Default)]
+struct Model {
+	owners: BTreeMap<u64, AccountId>,
+	prices: BTreeMap<u64, u64>,
+	free: BTreeMap<AccountId, u64>,
+	reserved: BTreeMap<AccountId, u64>,
+	births: BTreeMap<u64, u64>,
+	last_bred: BTreeMap<u64, u64>,
+	next_id: u64,
+}
+
+impl Model {
+	fn new() -> Self {
+		let mut model = Model::default();
+		for who in ACCOUNTS.iter() {
+			model.free.insert(who.clone(), INITIAL_BALANCE);
+			model.reserved.insert(who.clone(), 0);
+		}
+		model
+	}
+
+	fn owner(&self, kitty_id: u64) -> Option<&AccountId> {
+		self.owners.get(&kitty_id)
+	}
+
+	fn can_mint(&self, owner: &AccountId) -> bool {
+		self.free[owner] >= Pledge::get()
+	}
+
+	fn mint(&mut self, owner: &AccountId) {
+		*self.free.get_mut(owner).unwrap() -= Pledge::get();
+		*self.reserved.get_mut(owner).unwrap() += Pledge::get();
+		self.owners.insert(self.next_id, owner.clone());
+		self.births.insert(self.next_id, System::block_number());
+		self.next_id += 1;
+	}
+
+	/// Whether the breeding cooldown of a kitty passed, shortened by 10% for every level of
+	/// the experience it gained since its birth, up to 50%.
+	fn can_breed(&self, kitty_id: u64) -> bool {
+		let now = System::block_number();
+		let last_bred = match self.last_bred.get(&kitty_id) {
+			Some(block) => *block,
+			None => return true,
+		};
+		let xp = (now - self.births[&kitty_id]) * ExperiencePerBlock::get();
+		let level = LevelThresholds::get().iter().filter(|threshold| xp >= **threshold).count();
+		let percent = 100 - (level as u64 * 10).min(50);
+		now >= last_bred + BreedCooldown::get() * percent / 100
+	}
+
+	fn breed(&mut self, owner: &AccountId, kid1: u64, kid2: u64) {
+		let now = System::block_number();
+		self.last_bred.insert(kid1, now);
+		self.last_bred.insert(kid2, now);
+		self.mint(owner);
+	}
+
+	fn transfer(&mut self, kitty_id: u64, to: &AccountId) {
+		// The pledge never changes here, so the deposit simply follows the kitty.
+		let from = self.owners.insert(kitty_id, to.clone()).unwrap();
+		*self.reserved.get_mut(&from).unwrap() -= Pledge::get();
+		*self.reserved.get_mut(to).unwrap() += Pledge::get();
+		self.prices.remove(&kitty_id);
+	}
+
+	fn pay(&mut self, from: &AccountId, to: &AccountId, amount: u64) {
+		*self.free.get_mut(from).unwrap() -= amount;
+		*self.free.get_mut(to).unwrap() += amount;
+	}
+}
+
+/// Runs the action on the chain, asserting it succeeds or fails as the model predicts, and
+/// updates the model when it succeeded.
+fn apply(model: &mut Model, action: &Action) {
+	match action.clone() {
+		Action::Create(who) => {
+			let who = &ACCOUNTS[who];
+			let result = Kitties::create_kitty(Origin::signed(who.clone()));
+			assert_eq!(result.is_ok(), model.can_mint(who), "create needs a free pledge");
+			if result.is_ok() {
+				model.mint(who);
+			}
+		},
+		Action::Sell(who, kitty_id, price) => {
+			let who = &ACCOUNTS[who];
+			let result = Kitties::sell_kitty(Origin::signed(who.clone()), kitty_id, price, None);
+			assert_eq!(result.is_ok(), model.owner(kitty_id) == Some(who), "only the owner sells");
+			if result.is_ok() {
+				model.prices.insert(kitty_id, price);
+			}
+		},
+		Action::Buy(who, kitty_id, max_price) => {
+			let buyer = &ACCOUNTS[who];
+			let nonce = Kitties::listing_nonce(kitty_id);
+			let result =
+				Kitties::buy_kitty(Origin::signed(buyer.clone()), kitty_id, max_price, nonce);
+			let ask = model.prices.get(&kitty_id).copied();
+			let free = model.free[buyer];
+			if model.owner(kitty_id).map_or(true, |owner| owner == buyer) ||
+				ask.map_or(true, |ask| ask > max_price || free < ask)
+			{
+				assert!(result.is_err(), "the buy must fail");
+			} else if free >= ask.unwrap() + ExistentialDeposit::get() {
+				// The price is paid with a keep-alive transfer.
+				assert!(result.is_ok(), "the buy must succeed");
+			}
+			if result.is_ok() {
+				let seller = model.owner(kitty_id).unwrap().clone();
+				model.pay(buyer, &seller, ask.unwrap());
+				model.transfer(kitty_id, buyer);
+			}
+		},
+		Action::Transfer(from, to, kitty_id) => {
+			let (from, to) = (&ACCOUNTS[from], &ACCOUNTS[to]);
+			let result = Kitties::transfer(Origin::signed(from.clone()), to.clone(), kitty_id);
+			assert_eq!(
+				result.is_ok(),
+				model.owner(kitty_id) == Some(from) && from != to,
+				"only the owner may transfer to someone else"
+			);
+			if result.is_ok() {
+				model.transfer(kitty_id, to);
+			}
+		},
+		Action::Breed(who, kid1, kid2) => {
+			let who = &ACCOUNTS[who];
+			let result = Kitties::breed_kitty(Origin::signed(who.clone()), kid1, kid2);
+			let owns_both = model.owner(kid1) == Some(who) && model.owner(kid2) == Some(who);
+			assert_eq!(
+				result.is_ok(),
+				owns_both && model.can_breed(kid1) && model.can_breed(kid2) && model.can_mint(who),
+				"only the owner of both rested parents may breed, pledging for the child"
+			);
+			if result.is_ok() {
+				model.breed(who, kid1, kid2);
+			}
+		},
+		Action::NextBlock => run_to_block(System::block_number() + 1),
+	}
+	check_model(model);
+}
+
+fn check_model(model: &Model) {
+	assert_eq!(Kitties::kitty_cnt(), model.next_id);
+	for (kitty_id, owner) in model.owners.iter() {
+		let kitty = Kitties::kitties(kitty_id).expect("the model kitty should exist");
+		assert_eq!(&kitty.owner, owner);
+		assert_eq!(kitty.price, model.prices.get(kitty_id).copied());
+	}
+	for who in ACCOUNTS.iter() {
+		assert_eq!(Balances::free_balance(who), model.free[who]);
+		assert_eq!(Balances::reserved_balance(who), model.reserved[who]);
+		let owned = model.owners.values().filter(|owner| *owner == who).count();
+		assert_eq!(Kitties::owned_kitties(who).len(), owned);
+	}
+
+	// No extrinsic mints or burns funds, and every kitty holds exactly one pledge.
+	assert_eq!(Balances::total_issuance(), INITIAL_BALANCE * ACCOUNTS.len() as u64);
+	let reserved: u64 = ACCOUNTS.iter().map(Balances::reserved_balance).sum();
+	assert_eq!(reserved, Pledge::get() * model.owners.len() as u64);
+	assert_ok!(Kitties::do_try_state());
+}
+
+proptest! {
+	#[test]
+	fn kitties_state_machine_should_match_model(
+		actions in prop::collection::vec(action(), 1..60)
+	) {
+		let balances = ACCOUNTS.iter().map(|who| (who.clone(), INITIAL_BALANCE)).collect();
+		new_test_ext_with(balances, vec![]).execute_with(|| {
+			let mut model = Model::new();
+			for action in actions.iter() {
+				apply(&mut model, action);
+			}
+		});
+	}
+}
@@ -1,7 +1,17 @@
 #![cfg(test)]
 
-use crate::{mock::*, pallet::Error,pallet::KittyCnt};
-use frame_support::{assert_noop, assert_ok,assert_err};
+use crate::{mock::*, pallet::Error,pallet::KittyCnt, BatchOperation, PriceChange};
+use codec::Encode;
+use frame_support::{
+	dispatch::{DispatchResult, UnfilteredDispatchable},
+	traits::{Hooks, ReservableCurrency, StorageVersion},
+	unsigned::ValidateUnsigned,
+};
+use sp_core::{sr25519, Pair};
+use sp_runtime::{
+	transaction_validity::{InvalidTransaction, TransactionSource},
+	MultiSignature,
+};
 
 #[test]
 fn should_build_genesis_kitties() {
@@ -136,7 +146,7 @@ fn sell_kitty_unit_error_by_kitty_not_exist() {
 	new_test_ext().execute_with(|| {
 		// should failed, kitty is not exist
 		assert_noop!(
-			Kitties::sell_kitty(Origin::signed(ALICE), 3, Some(500)),
+			Kitties::sell_kitty(Origin::signed(ALICE), 3, 500, None),
 			Error::<Test>::KittyNotExist
 		);
 
@@ -148,7 +158,7 @@ fn sell_kitty_unit_error_by_kitty_not_owner() {
 	new_test_ext().execute_with(|| {
 		// should failed, kitty is not exist
 		assert_noop!(
-			Kitties::sell_kitty(Origin::signed(ALICE), 1, Some(500)),
+			Kitties::sell_kitty(Origin::signed(ALICE), 1, 500, None),
 			Error::<Test>::NotKittyOwner
 		);
 
@@ -159,9 +169,14 @@ fn sell_kitty_unit_error_by_kitty_not_owner() {
 fn sell_kitty_should_work() {
 	new_test_ext().execute_with(|| {
 
-		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, Some(500)));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, None));
 
-		System::assert_has_event(Event::Kitties(crate::Event::PriceSet(ALICE, 0, Some(500))));
+		System::assert_has_event(Event::Kitties(crate::Event::PriceSet(
+			ALICE,
+			0,
+			Some(500),
+			PriceChange::Listed,
+		)));
 
 		let kitty = Kitties::kitties(0).expect("should found the kitty");
 		assert_eq!(kitty.price, Some(500));
@@ -173,7 +188,7 @@ fn buy_kitty_unit_error_by_buyer_is_kitty_owner() {
 	new_test_ext().execute_with(|| {
 
 		assert_noop!(
-			Kitties::buy_kitty(Origin::signed(ALICE), 0, 500),
+			Kitties::buy_kitty(Origin::signed(ALICE), 0, 500, 0),
 			Error::<Test>::BuyerIsKittyOwner
 		);
 
@@ -185,7 +200,7 @@ fn buy_kitty_unit_error_by_kitty_not_for_sale() {
 	new_test_ext().execute_with(|| {
 
 		assert_noop!(
-			Kitties::buy_kitty(Origin::signed(BOB), 0, 500),
+			Kitties::buy_kitty(Origin::signed(BOB), 0, 500, 0),
 			Error::<Test>::KittyNotForSale
 		);
 
@@ -195,7 +210,10 @@ fn buy_kitty_unit_error_by_kitty_not_for_sale() {
 #[test]
 fn buy_kitty_unit_error_by_kitty_not_exist() {
 	new_test_ext().execute_with(|| {
-		assert_noop!(Kitties::buy_kitty(Origin::signed(BOB), 2, 500), Error::<Test>::KittyNotExist);
+		assert_noop!(
+			Kitties::buy_kitty(Origin::signed(BOB), 2, 500, 0),
+			Error::<Test>::KittyNotExist
+		);
 
 	});
 }
@@ -204,10 +222,10 @@ fn buy_kitty_unit_error_by_kitty_not_exist() {
 fn buy_kitty_unit_error_by_kitty_bid_price_too_low() {
 	new_test_ext().execute_with(|| {
 
-		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, Some(100)));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
 
 		assert_noop!(
-			Kitties::buy_kitty(Origin::signed(BOB), 0, 1),
+			Kitties::buy_kitty(Origin::signed(BOB), 0, 1, Kitties::listing_nonce(0)),
 			Error::<Test>::KittyBidPriceTooLow
 		);
 
@@ -219,10 +237,10 @@ fn buy_kitty_unit_error_by_kitty_bid_price_too_low() {
 fn buy_kitty_unit_error_by_not_enough_balance() {
 	new_test_ext().execute_with(|| {
 
-		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, Some(100)));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 20000, None));
 
 		assert_noop!(
-			Kitties::buy_kitty(Origin::signed(BOB), 0, u64::MAX),
+			Kitties::buy_kitty(Origin::signed(BOB), 0, u64::MAX, Kitties::listing_nonce(0)),
 			Error::<Test>::NotEnoughBalance
 		);
 
@@ -237,17 +255,20 @@ fn buy_kitty_should_work() {
 	new_test_ext().execute_with(|| {
 
 		// set price
-		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, Some(100)));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
 
-		assert_ok!(Kitties::buy_kitty(Origin::signed(BOB), 0, 100));
+		assert_ok!(Kitties::buy_kitty(Origin::signed(BOB), 0, 100, Kitties::listing_nonce(0)));
 
 		System::assert_has_event(Event::Kitties(crate::Event::Bought(BOB, ALICE, 0, 100)));
 
-		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 0, Some(100)));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 0, 100, None));
 
-		assert_ok!(Kitties::buy_kitty(Origin::signed(ALICE), 0, 1000));
+		// the buyer pays the asking price, not its maximum price
+		assert_ok!(Kitties::buy_kitty(Origin::signed(ALICE), 0, 1000, Kitties::listing_nonce(0)));
 
-		System::assert_has_event(Event::Kitties(crate::Event::Bought(ALICE, BOB, 0, 1000)));
+		System::assert_has_event(Event::Kitties(crate::Event::Bought(ALICE, BOB, 0, 100)));
+		assert_eq!(Balances::free_balance(ALICE), 5000);
+		assert_eq!(Balances::free_balance(BOB), 10000 - 5000);
 
 		// check kitty information
 		let kitty = Kitties::kitties(0).expect("should found the kitty");
@@ -301,4 +322,1508 @@ fn breed_kitty_should_work() {
 		assert_eq!(kitty.price, None);
 		assert_eq!(kitty.owner, ALICE);
 	});
-}
\ No newline at end of file
+}
+
+#[test]
+fn set_pledge_error_by_bad_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::set_pledge(Origin::signed(ALICE), 6000),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn set_pledge_should_work() {
+	new_test_ext_for_create().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 6000));
+
+		System::assert_has_event(Event::Kitties(crate::Event::PledgeSet(6000)));
+		assert_eq!(Kitties::pledge(), 6000);
+
+		// new kitties reserve the new pledge
+		assert_ok!(Kitties::create_kitty(Origin::signed(ALICE)));
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").deposit, 6000);
+		assert_eq!(Balances::reserved_balance(ALICE), 6000);
+	});
+}
+
+#[test]
+fn reprice_deposit_error_by_deposit_up_to_date() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::reprice_deposit(Origin::signed(BOB), 0),
+			Error::<Test>::DepositUpToDate
+		);
+	});
+}
+
+#[test]
+fn reprice_deposit_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 6000));
+
+		assert_ok!(Kitties::reprice_deposit(Origin::signed(BOB), 0));
+
+		System::assert_has_event(Event::Kitties(crate::Event::DepositRepriced(0, 5000, 6000)));
+
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").deposit, 6000);
+		assert_eq!(Balances::reserved_balance(ALICE), 6000);
+		// ALICE paid the reward to BOB
+		assert_eq!(Balances::free_balance(ALICE), 10000 - 6000 - RepriceReward::get());
+		assert_eq!(Balances::free_balance(BOB), 10000 - 5000 + RepriceReward::get());
+	});
+}
+
+#[test]
+fn transfer_kitty_should_reconcile_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 4000));
+
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+
+		System::assert_has_event(Event::Kitties(crate::Event::DepositRepriced(0, 5000, 4000)));
+
+		let kitty = Kitties::kitties(0).expect("should found the kitty");
+		assert_eq!(kitty.deposit, 4000);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::reserved_balance(BOB), 5000 + 4000);
+		assert_eq!(Balances::free_balance(BOB), 5000 + 1000);
+	});
+}
+
+#[test]
+fn owned_kitties_should_follow_transfers() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Kitties::owned_kitties(ALICE).into_inner(), vec![0]);
+		assert_eq!(Kitties::owned_kitties(BOB).into_inner(), vec![1]);
+
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+
+		assert!(Kitties::owned_kitties(ALICE).is_empty());
+		assert_eq!(Kitties::owned_kitties(BOB).into_inner(), vec![1, 0]);
+	});
+}
+
+// Releases ALICE's deposit and moves all her funds away so the account is reaped.
+fn reap_alice() {
+	assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+	assert_ok!(Kitties::reprice_deposit(Origin::signed(ALICE), 0));
+	assert_ok!(Balances::transfer(Origin::signed(ALICE), BOB, 10000));
+	assert!(!System::account_exists(&ALICE));
+}
+
+#[test]
+fn reaped_owner_should_orphan_kitties() {
+	new_test_ext().execute_with(|| {
+		reap_alice();
+		// the kitties are moved to the adoption pool in on_idle
+		assert_eq!(Kitties::reaped_owners(ALICE), Some(()));
+		assert_eq!(Kitties::adoption_pool(0), None);
+		run_to_block(2);
+
+		System::assert_has_event(Event::Kitties(crate::Event::KittyOrphaned(0, ALICE)));
+		assert_eq!(Kitties::adoption_pool(0), Some(()));
+		assert_eq!(Kitties::reaped_owners(ALICE), None);
+		assert!(Kitties::owned_kitties(ALICE).is_empty());
+		let pool = Kitties::adoption_pool_account();
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, pool);
+		assert_eq!(Kitties::kitties_owned(0), Some(pool));
+
+		// the old owner cannot trade the kitty after coming back to life
+		assert_ok!(Balances::transfer(Origin::signed(BOB), ALICE, 1000));
+		assert_noop!(
+			Kitties::transfer(Origin::signed(ALICE), BOB, 0),
+			Error::<Test>::KittyOrphaned
+		);
+	});
+}
+
+#[test]
+fn reprice_deposit_error_by_kitty_orphaned() {
+	new_test_ext().execute_with(|| {
+		reap_alice();
+		run_to_block(2);
+		assert_ok!(Kitties::set_pledge(Origin::root(), 5000));
+		assert_ok!(Balances::transfer(Origin::signed(BOB), ALICE, 5000));
+
+		assert_noop!(
+			Kitties::reprice_deposit(Origin::signed(BOB), 0),
+			Error::<Test>::KittyOrphaned
+		);
+	});
+}
+
+#[test]
+fn migration_should_index_owned_kitties() {
+	new_test_ext().execute_with(|| {
+		crate::OwnedKitties::<Test>::remove(ALICE);
+		crate::OwnedKitties::<Test>::remove(BOB);
+		StorageVersion::new(0).put::<Kitties>();
+
+		Kitties::on_runtime_upgrade();
+
+		assert_eq!(Kitties::owned_kitties(ALICE).into_inner(), vec![0]);
+		assert_eq!(Kitties::owned_kitties(BOB).into_inner(), vec![1]);
+		assert_eq!(StorageVersion::get::<Kitties>(), StorageVersion::new(1));
+
+		// running it again changes nothing
+		Kitties::on_runtime_upgrade();
+		assert_eq!(Kitties::owned_kitties(ALICE).into_inner(), vec![0]);
+	});
+}
+
+#[test]
+fn adopt_kitty_error_by_kitty_not_orphaned() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::adopt_kitty(Origin::signed(BOB), 0),
+			Error::<Test>::KittyNotOrphaned
+		);
+	});
+}
+
+#[test]
+fn adopt_kitty_error_by_kitty_frozen() {
+	new_test_ext().execute_with(|| {
+		reap_alice();
+		run_to_block(2);
+		assert_ok!(Kitties::freeze_kitty(Origin::root(), 0));
+		assert_noop!(Kitties::adopt_kitty(Origin::signed(BOB), 0), Error::<Test>::KittyFrozen);
+	});
+}
+
+#[test]
+fn adopt_kitty_error_by_account_blacklisted() {
+	new_test_ext().execute_with(|| {
+		reap_alice();
+		run_to_block(2);
+		assert_ok!(Kitties::blacklist_account(Origin::root(), BOB));
+		assert_noop!(
+			Kitties::adopt_kitty(Origin::signed(BOB), 0),
+			Error::<Test>::AccountBlacklisted
+		);
+	});
+}
+
+#[test]
+fn adopt_kitty_should_work() {
+	new_test_ext().execute_with(|| {
+		reap_alice();
+		run_to_block(2);
+		assert_ok!(Kitties::set_pledge(Origin::root(), 5000));
+
+		assert_ok!(Kitties::adopt_kitty(Origin::signed(BOB), 0));
+
+		System::assert_has_event(Event::Kitties(crate::Event::KittyAdopted(BOB, 0)));
+		assert_eq!(Kitties::adoption_pool(0), None);
+		assert_eq!(Kitties::kitties_owned(0), Some(BOB));
+
+		let kitty = Kitties::kitties(0).expect("should found the kitty");
+		assert_eq!(kitty.owner, BOB);
+		assert_eq!(kitty.deposit, 5000);
+		assert_eq!(Balances::reserved_balance(BOB), 10000);
+		assert_eq!(Kitties::owned_kitties(BOB).into_inner(), vec![1, 0]);
+	});
+}
+
+#[test]
+fn start_raffle_error_by_kitty_not_owner() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::start_raffle(Origin::signed(ALICE), 1, 100, 5, 10),
+			Error::<Test>::NotKittyOwner
+		);
+	});
+}
+
+#[test]
+fn start_raffle_error_by_raffle_end_in_past() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 5, 1),
+			Error::<Test>::RaffleEndInPast
+		);
+	});
+}
+
+#[test]
+fn start_raffle_error_by_invalid_raffle_tickets() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 1, 10),
+			Error::<Test>::InvalidRaffleTickets
+		);
+		assert_noop!(
+			Kitties::start_raffle(Origin::signed(ALICE), 0, 100, MaxRaffleTickets::get() + 1, 10),
+			Error::<Test>::InvalidRaffleTickets
+		);
+	});
+}
+
+#[test]
+fn raffled_kitty_should_be_locked() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 5, 10));
+
+		System::assert_has_event(Event::Kitties(crate::Event::RaffleStarted(ALICE, 0, 0)));
+
+		assert_noop!(
+			Kitties::transfer(Origin::signed(ALICE), BOB, 0),
+			Error::<Test>::KittyInRaffle
+		);
+		assert_noop!(
+			Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, None),
+			Error::<Test>::KittyInRaffle
+		);
+	});
+}
+
+#[test]
+fn buy_raffle_ticket_error_by_buyer_is_kitty_owner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 5, 10));
+		assert_noop!(
+			Kitties::buy_raffle_ticket(Origin::signed(ALICE), 0),
+			Error::<Test>::BuyerIsKittyOwner
+		);
+	});
+}
+
+#[test]
+fn buy_raffle_ticket_error_by_raffle_sold_out() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 2, 10));
+		assert_ok!(Kitties::buy_raffle_ticket(Origin::signed(BOB), 0));
+		assert_ok!(Kitties::buy_raffle_ticket(Origin::signed(BOB), 0));
+		assert_noop!(
+			Kitties::buy_raffle_ticket(Origin::signed(BOB), 0),
+			Error::<Test>::RaffleSoldOut
+		);
+	});
+}
+
+#[test]
+fn buy_raffle_ticket_error_by_raffle_ended() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 5, 10));
+		System::set_block_number(10);
+		assert_noop!(
+			Kitties::buy_raffle_ticket(Origin::signed(BOB), 0),
+			Error::<Test>::RaffleEnded
+		);
+	});
+}
+
+#[test]
+fn raffle_should_draw_winner() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 5, 10));
+		assert_ok!(Kitties::buy_raffle_ticket(Origin::signed(BOB), 0));
+		assert_ok!(Kitties::buy_raffle_ticket(Origin::signed(BOB), 0));
+
+		System::assert_has_event(Event::Kitties(crate::Event::RaffleTicketBought(BOB, 0)));
+		assert_eq!(Balances::reserved_balance(BOB), 5000 + 200);
+
+		run_to_block(10);
+
+		System::assert_has_event(Event::Kitties(crate::Event::RaffleDrawn(0, BOB, 0, 200)));
+		assert_eq!(Kitties::raffles(0), None);
+		assert_eq!(Kitties::kitty_raffle(0), None);
+
+		// the kitty and its pledge moved to the winner, the proceeds to the owner
+		let kitty = Kitties::kitties(0).expect("should found the kitty");
+		assert_eq!(kitty.owner, BOB);
+		assert_eq!(Balances::reserved_balance(BOB), 10000);
+		assert_eq!(Balances::free_balance(BOB), 10000 - 5000 - 200);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 5000 + 200);
+	});
+}
+
+#[test]
+fn orphaned_kitty_should_cancel_raffle() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::reprice_deposit(Origin::signed(ALICE), 0));
+		assert_ok!(Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 5, 10));
+		assert_ok!(Kitties::buy_raffle_ticket(Origin::signed(BOB), 0));
+		assert_ok!(Balances::transfer(Origin::signed(ALICE), BOB, 10000));
+		run_to_block(2);
+
+		System::assert_has_event(Event::Kitties(crate::Event::RaffleCancelled(0)));
+		assert_eq!(Kitties::raffles(0), None);
+		assert_eq!(Kitties::kitty_raffle(0), None);
+		assert!(Kitties::raffles_ending(10).is_empty());
+		assert_eq!(Balances::reserved_balance(BOB), 5000);
+
+		assert_ok!(Kitties::adopt_kitty(Origin::signed(BOB), 0));
+		run_to_block(10);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, BOB);
+	});
+}
+
+#[test]
+fn raffle_should_refund_tickets_below_minimum() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::start_raffle(Origin::signed(ALICE), 0, 100, 5, 10));
+		assert_ok!(Kitties::buy_raffle_ticket(Origin::signed(BOB), 0));
+
+		run_to_block(10);
+
+		System::assert_has_event(Event::Kitties(crate::Event::RaffleCancelled(0)));
+		assert_eq!(Balances::reserved_balance(BOB), 5000);
+		assert_eq!(Balances::free_balance(BOB), 5000);
+
+		// the kitty stays with its owner and is unlocked again
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, ALICE);
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+	});
+}
+
+#[test]
+fn challenge_error_by_cannot_battle_own_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create_kitty(Origin::signed(ALICE)));
+		assert_noop!(
+			Kitties::challenge(Origin::signed(ALICE), 0, 2),
+			Error::<Test>::CannotBattleOwnKitty
+		);
+	});
+}
+
+#[test]
+fn accept_challenge_error_by_challenge_not_exist() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::accept_challenge(Origin::signed(BOB), 1, 0),
+			Error::<Test>::ChallengeNotExist
+		);
+	});
+}
+
+#[test]
+fn accept_challenge_error_by_challenger_transferred() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+		assert_ok!(Kitties::transfer(Origin::signed(BOB), ALICE, 1));
+
+		assert_noop!(
+			Kitties::accept_challenge(Origin::signed(ALICE), 1, 0),
+			Error::<Test>::ChallengeNotExist
+		);
+	});
+}
+
+#[test]
+fn challenge_error_by_challenge_exists() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		assert_noop!(
+			Kitties::challenge(Origin::signed(ALICE), 0, 1),
+			Error::<Test>::ChallengeExists
+		);
+	});
+}
+
+#[test]
+fn challenge_error_by_too_many_challenges() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 1000));
+		assert_ok!(Kitties::create_kitty(Origin::signed(BOB)));
+		assert_ok!(Kitties::create_kitty(Origin::signed(BOB)));
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 2));
+		assert_noop!(
+			Kitties::challenge(Origin::signed(ALICE), 0, 3),
+			Error::<Test>::TooManyChallenges
+		);
+	});
+}
+
+#[test]
+fn challenge_error_by_kitty_frozen() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::freeze_kitty(Origin::root(), 1));
+		assert_noop!(Kitties::challenge(Origin::signed(ALICE), 0, 1), Error::<Test>::KittyFrozen);
+	});
+}
+
+#[test]
+fn accept_challenge_error_by_kitty_frozen() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		assert_ok!(Kitties::freeze_kitty(Origin::root(), 0));
+		assert_noop!(
+			Kitties::accept_challenge(Origin::signed(BOB), 1, 0),
+			Error::<Test>::KittyFrozen
+		);
+	});
+}
+
+#[test]
+fn withdraw_challenge_should_refund_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		assert_eq!(Balances::reserved_balance(ALICE), Pledge::get() + ChallengeDeposit::get());
+
+		assert_noop!(
+			Kitties::withdraw_challenge(Origin::signed(BOB), 0, 1),
+			Error::<Test>::NotKittyOwner
+		);
+		assert_ok!(Kitties::withdraw_challenge(Origin::signed(ALICE), 0, 1));
+		System::assert_has_event(Event::Kitties(crate::Event::ChallengeWithdrawn(ALICE, 0, 1)));
+		assert_eq!(Kitties::challenges(1, 0), None);
+		assert!(Kitties::challenges_issued(0).is_empty());
+		assert!(Kitties::challenges_received(1).is_empty());
+		assert_eq!(Balances::reserved_balance(ALICE), Pledge::get());
+	});
+}
+
+#[test]
+fn transfer_should_clear_challenges() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		assert_ok!(Kitties::transfer(Origin::signed(BOB), ALICE, 1));
+
+		assert_eq!(Kitties::challenges(1, 0), None);
+		assert!(Kitties::challenges_issued(0).is_empty());
+		assert!(Kitties::challenges_received(1).is_empty());
+		assert_eq!(Balances::reserved_balance(ALICE), 2 * Pledge::get());
+	});
+}
+
+#[test]
+fn burn_kitty_should_clear_challenges() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		assert_ok!(Kitties::burn_kitty(Origin::signed(BOB), 1));
+
+		assert_eq!(Kitties::challenges(1, 0), None);
+		assert!(Kitties::challenges_issued(0).is_empty());
+		assert_eq!(Balances::reserved_balance(ALICE), Pledge::get());
+	});
+}
+
+#[test]
+fn battle_should_update_records() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::challenge(Origin::signed(ALICE), 0, 1));
+		System::assert_has_event(Event::Kitties(crate::Event::ChallengeIssued(ALICE, 0, 1)));
+
+		assert_ok!(Kitties::accept_challenge(Origin::signed(BOB), 1, 0));
+		assert_eq!(Kitties::challenges(1, 0), None);
+		assert_eq!(Balances::reserved_balance(ALICE), Pledge::get());
+
+		let (r0, r1) = (Kitties::battle_records(0), Kitties::battle_records(1));
+		assert_eq!(r0.wins + r1.wins, 1);
+		assert_eq!(r0.losses + r1.losses, 1);
+		// evenly rated kitties move by half the K factor
+		assert_eq!(r0.rating + r1.rating, 2 * crate::INITIAL_RATING);
+		assert_eq!(r0.rating.max(r1.rating), crate::INITIAL_RATING + crate::ELO_K_FACTOR / 2);
+	});
+}
+
+#[test]
+fn create_tournament_error_by_bad_origin() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::create_tournament(Origin::signed(ALICE), 100, 10),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn create_tournament_error_by_tournament_slot_taken() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create_tournament(Origin::root(), 100, 10));
+		assert_noop!(
+			Kitties::create_tournament(Origin::root(), 100, 10),
+			Error::<Test>::TournamentSlotTaken
+		);
+	});
+}
+
+#[test]
+fn enter_tournament_error_by_kitty_in_tournament() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create_tournament(Origin::root(), 100, 10));
+		assert_ok!(Kitties::enter_tournament(Origin::signed(ALICE), 0, 0));
+
+		assert_noop!(
+			Kitties::enter_tournament(Origin::signed(ALICE), 0, 0),
+			Error::<Test>::KittyInTournament
+		);
+		assert_noop!(
+			Kitties::transfer(Origin::signed(ALICE), BOB, 0),
+			Error::<Test>::KittyInTournament
+		);
+	});
+}
+
+#[test]
+fn tournament_should_pay_prize_pool() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create_tournament(Origin::root(), 100, 10));
+		System::assert_has_event(Event::Kitties(crate::Event::TournamentCreated(0, 100, 10)));
+
+		assert_ok!(Kitties::enter_tournament(Origin::signed(ALICE), 0, 0));
+		assert_ok!(Kitties::enter_tournament(Origin::signed(BOB), 0, 1));
+		assert_eq!(Balances::reserved_balance(ALICE), 5000 + 100);
+
+		run_to_block(10);
+
+		assert_eq!(Kitties::tournaments(0), None);
+		assert_eq!(Kitties::kitty_tournament(0), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 5000);
+		assert_eq!(Balances::reserved_balance(BOB), 5000);
+
+		// the winner took both entry fees
+		let (alice, bob) = (Balances::free_balance(ALICE), Balances::free_balance(BOB));
+		assert_eq!(alice + bob, 10000);
+		assert_eq!(alice.max(bob), 5100);
+		let champion = if alice > bob { 0 } else { 1 };
+		let owner = if alice > bob { ALICE } else { BOB };
+		System::assert_has_event(Event::Kitties(crate::Event::TournamentWon(
+			0, champion, owner, 200,
+		)));
+		assert_eq!(Kitties::battle_records(champion).wins, 1);
+	});
+}
+
+#[test]
+fn tournament_should_refund_entry_fees_below_two_entrants() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::create_tournament(Origin::root(), 100, 10));
+		assert_ok!(Kitties::enter_tournament(Origin::signed(ALICE), 0, 0));
+
+		run_to_block(10);
+
+		System::assert_has_event(Event::Kitties(crate::Event::TournamentCancelled(0)));
+		assert_eq!(Balances::free_balance(ALICE), 5000);
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+	});
+}
+
+#[test]
+fn experience_should_accrue_across_transfers() {
+	new_test_ext().execute_with(|| {
+		// genesis kitties were minted at block 0
+		assert_eq!(Kitties::experience(&0), 1);
+		assert_eq!(Kitties::level(&0), 0);
+
+		System::set_block_number(11);
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+		assert_eq!(Kitties::experience(&0), 11);
+		assert_eq!(Kitties::level(&0), 1);
+
+		assert_ok!(Kitties::refresh_experience(Origin::signed(BOB), 0));
+		System::assert_has_event(Event::Kitties(crate::Event::LevelUp(0, 1)));
+		assert_eq!(Kitties::kitty_experience(0).xp, 11);
+		assert_eq!(Kitties::kitty_experience(0).last_update, 11);
+
+		System::set_block_number(101);
+		assert_eq!(Kitties::level(&0), 2);
+	});
+}
+
+#[test]
+fn refresh_experience_error_by_kitty_not_exist() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::refresh_experience(Origin::signed(ALICE), 3),
+			Error::<Test>::KittyNotExist
+		);
+	});
+}
+
+#[test]
+fn breed_kitty_error_by_kitty_breeding_cooldown() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::transfer(Origin::signed(BOB), ALICE, 1));
+		assert_ok!(Kitties::breed_kitty(Origin::signed(ALICE), 0, 1));
+
+		assert_noop!(
+			Kitties::breed_kitty(Origin::signed(ALICE), 0, 1),
+			Error::<Test>::KittyBreedingCooldown
+		);
+	});
+}
+
+#[test]
+fn breed_cooldown_should_shorten_with_level() {
+	new_test_ext().execute_with(|| {
+		// leave ALICE enough free balance to breed twice
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::transfer(Origin::signed(BOB), ALICE, 1));
+		assert_ok!(Kitties::breed_kitty(Origin::signed(ALICE), 0, 1));
+
+		// both parents are level 1 from block 10 on, shortening the cooldown to 18 blocks
+		System::set_block_number(18);
+		assert_noop!(
+			Kitties::breed_kitty(Origin::signed(ALICE), 0, 1),
+			Error::<Test>::KittyBreedingCooldown
+		);
+
+		System::set_block_number(19);
+		assert_ok!(Kitties::breed_kitty(Origin::signed(ALICE), 0, 1));
+	});
+}
+
+#[test]
+fn buy_kitty_unit_error_by_listing_changed() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
+		let nonce = Kitties::listing_nonce(0);
+
+		// the seller reprices in the same block
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
+
+		assert_noop!(
+			Kitties::buy_kitty(Origin::signed(BOB), 0, 100, nonce),
+			Error::<Test>::ListingChanged
+		);
+	});
+}
+
+#[test]
+fn buy_kitty_unit_error_by_not_enough_balance_for_deposit_top_up() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 5000, None));
+		assert_ok!(Kitties::set_pledge(Origin::root(), 5001));
+
+		// BOB can afford the price but not the higher pledge
+		assert_noop!(
+			Kitties::buy_kitty(Origin::signed(BOB), 0, 5000, Kitties::listing_nonce(0)),
+			Error::<Test>::NotEnoughBalance
+		);
+	});
+}
+
+#[test]
+fn sell_kitty_should_report_repricing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, None));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 600, None));
+
+		System::assert_last_event(Event::Kitties(crate::Event::PriceSet(
+			ALICE,
+			0,
+			Some(600),
+			PriceChange::Repriced,
+		)));
+	});
+}
+
+#[test]
+fn sell_kitty_unit_error_by_listing_expiry_in_past() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, Some(1)),
+			Error::<Test>::ListingExpiryInPast
+		);
+	});
+}
+
+#[test]
+fn cancel_listing_error_by_kitty_not_for_sale() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Kitties::cancel_listing(Origin::signed(ALICE), 0),
+			Error::<Test>::KittyNotForSale
+		);
+	});
+}
+
+#[test]
+fn cancel_listing_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, Some(10)));
+		assert_ok!(Kitties::cancel_listing(Origin::signed(ALICE), 0));
+
+		System::assert_last_event(Event::Kitties(crate::Event::PriceSet(
+			ALICE,
+			0,
+			None,
+			PriceChange::Delisted,
+		)));
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, None);
+		assert_eq!(Kitties::listing_expiry(0), None);
+	});
+}
+
+#[test]
+fn expired_listing_should_not_be_for_sale() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, Some(10)));
+		let nonce = Kitties::listing_nonce(0);
+
+		System::set_block_number(10);
+		assert_noop!(
+			Kitties::buy_kitty(Origin::signed(BOB), 0, 500, nonce),
+			Error::<Test>::KittyNotForSale
+		);
+		// nor can it be cancelled before it is purged
+		assert_noop!(
+			Kitties::cancel_listing(Origin::signed(ALICE), 0),
+			Error::<Test>::KittyNotForSale
+		);
+
+		// listing again after expiry counts as a new listing
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, None));
+		System::assert_last_event(Event::Kitties(crate::Event::PriceSet(
+			ALICE,
+			0,
+			Some(500),
+			PriceChange::Listed,
+		)));
+	});
+}
+
+#[test]
+fn on_idle_should_purge_expired_listings() {
+	new_test_ext().execute_with(|| {
+		// the sweep starts with the first listing that expires
+		Kitties::on_idle(1, u64::MAX);
+		assert_eq!(Kitties::next_expiry_block(), None);
+
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, Some(10)));
+		assert_eq!(Kitties::next_expiry_block(), Some(1));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 1, 500, Some(10)));
+		// repricing drops the old expiry
+		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 1, 600, Some(20)));
+		assert_eq!(Kitties::expiring_listings(10).into_inner(), vec![0]);
+
+		System::set_block_number(10);
+		Kitties::on_idle(10, u64::MAX);
+
+		System::assert_has_event(Event::Kitties(crate::Event::PriceSet(
+			ALICE,
+			0,
+			None,
+			PriceChange::Expired,
+		)));
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, None);
+		assert_eq!(Kitties::kitties(1).expect("should found the kitty").price, Some(600));
+		assert_eq!(Kitties::next_expiry_block(), Some(11));
+		assert!(Kitties::expiring_listings(10).is_empty());
+	});
+}
+
+#[test]
+fn on_idle_should_purge_expired_listings_partially() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500, Some(10)));
+		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 1, 500, Some(10)));
+		Kitties::on_idle(9, u64::MAX);
+		assert_eq!(Kitties::next_expiry_block(), Some(10));
+
+		// enough weight for a single listing: 1 to look for reaped owners, 2 + 3 to take the
+		// block and 7 to purge a listing
+		System::set_block_number(10);
+		Kitties::on_idle(10, 13);
+
+		assert_eq!(Kitties::kitties(1).expect("should found the kitty").price, None);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, Some(500));
+		assert_eq!(Kitties::expiring_listings(10).into_inner(), vec![0]);
+		assert_eq!(Kitties::next_expiry_block(), Some(10));
+
+		Kitties::on_idle(10, u64::MAX);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, None);
+		assert_eq!(Kitties::next_expiry_block(), Some(11));
+	});
+}
+
+#[test]
+fn repricing_should_free_expiring_listing_slot() {
+	new_test_ext().execute_with(|| {
+		for price in 0..=MaxListingsExpiringPerBlock::get() as u64 {
+			assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 500 + price, Some(10)));
+		}
+		assert_eq!(Kitties::expiring_listings(10).into_inner(), vec![0]);
+
+		assert_ok!(Kitties::cancel_listing(Origin::signed(ALICE), 0));
+		assert!(!crate::pallet::ExpiringListings::<Test>::contains_key(10));
+	});
+}
+
+#[test]
+fn buy_kitty_should_record_sales_history() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		for price in vec![100, 200, 300] {
+			let owner = Kitties::kitties(0).expect("should found the kitty").owner;
+			let (seller, buyer) = if owner == ALICE { (ALICE, BOB) } else { (BOB, ALICE) };
+			assert_ok!(Kitties::sell_kitty(Origin::signed(seller), 0, price, None));
+			let nonce = Kitties::listing_nonce(0);
+			assert_ok!(Kitties::buy_kitty(Origin::signed(buyer), 0, price, nonce));
+		}
+		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 1, 50, None));
+		assert_ok!(Kitties::buy_kitty(Origin::signed(ALICE), 1, 50, Kitties::listing_nonce(1)));
+
+		// the per kitty history keeps the last two sales, oldest first
+		let prices: Vec<u64> = Kitties::kitty_sales(0).iter().map(|sale| sale.price).collect();
+		assert_eq!(prices, vec![200, 300]);
+		let sale = Kitties::kitty_sales(1).into_inner().pop().expect("should record the sale");
+		assert_eq!((sale.buyer, sale.seller, sale.block), (ALICE, BOB, 1));
+
+		// the market wide history keeps the last three trades
+		let prices: Vec<u64> = Kitties::recent_trades().iter().map(|sale| sale.price).collect();
+		assert_eq!(prices, vec![200, 300, 50]);
+	});
+}
+
+#[test]
+fn market_stats_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(Kitties::market_stats(10), crate::MarketStats::default());
+
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 300, None));
+		assert_ok!(Kitties::buy_kitty(Origin::signed(BOB), 0, 300, Kitties::listing_nonce(0)));
+
+		System::set_block_number(5);
+		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 1, 100, None));
+		assert_ok!(Kitties::buy_kitty(Origin::signed(ALICE), 1, 100, Kitties::listing_nonce(1)));
+
+		assert_eq!(
+			Kitties::market_stats(10),
+			crate::MarketStats {
+				trades: 2,
+				volume: 400,
+				floor_price: Some(100),
+				average_price: Some(200),
+			}
+		);
+
+		// only the trade within the last two blocks counts
+		assert_eq!(
+			Kitties::market_stats(2),
+			crate::MarketStats {
+				trades: 1,
+				volume: 100,
+				floor_price: Some(100),
+				average_price: Some(100),
+			}
+		);
+	});
+}
+
+#[test]
+fn create_kitties_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 100));
+		assert_ok!(Kitties::create_kitties(Origin::signed(ALICE), 3));
+
+		assert_eq!(KittyCnt::<Test>::get(), 5);
+		assert_eq!(Kitties::owned_kitties(ALICE).into_inner(), vec![0, 2, 3, 4]);
+		System::assert_has_event(Event::Kitties(crate::Event::Created(ALICE, 4)));
+		System::assert_last_event(Event::Kitties(crate::Event::BatchCompleted(
+			ALICE,
+			BatchOperation::Create,
+			3,
+		)));
+	});
+}
+
+#[test]
+fn create_kitties_error_by_batch_size() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(Kitties::create_kitties(Origin::signed(ALICE), 0), Error::<Test>::EmptyBatch);
+		assert_noop!(
+			Kitties::create_kitties(Origin::signed(ALICE), MaxKittyOwned::get() + 1),
+			Error::<Test>::BatchTooLarge
+		);
+	});
+}
+
+#[test]
+fn create_kitties_should_be_all_or_nothing() {
+	new_test_ext().execute_with(|| {
+		// ALICE can only afford one more deposit
+		assert_noop!(
+			Kitties::create_kitties(Origin::signed(ALICE), 2),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+		assert_eq!(KittyCnt::<Test>::get(), 2);
+	});
+}
+
+#[test]
+fn transfer_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 100));
+		assert_ok!(Kitties::create_kitties(Origin::signed(ALICE), 2));
+
+		assert_ok!(Kitties::transfer_batch(Origin::signed(ALICE), vec![(BOB, 0), (BOB, 2)]));
+
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, BOB);
+		assert_eq!(Kitties::kitties(2).expect("should found the kitty").owner, BOB);
+		assert_eq!(Kitties::owned_kitties(ALICE).into_inner(), vec![3]);
+		System::assert_has_event(Event::Kitties(crate::Event::Transferred(ALICE, BOB, 2)));
+		System::assert_last_event(Event::Kitties(crate::Event::BatchCompleted(
+			ALICE,
+			BatchOperation::Transfer,
+			2,
+		)));
+	});
+}
+
+#[test]
+fn transfer_batch_should_be_all_or_nothing() {
+	new_test_ext().execute_with(|| {
+		// the second kitty already belongs to BOB
+		assert_noop!(
+			Kitties::transfer_batch(Origin::signed(ALICE), vec![(BOB, 0), (BOB, 1)]),
+			Error::<Test>::NotKittyOwner
+		);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, ALICE);
+	});
+}
+
+#[test]
+fn set_prices_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 100));
+		assert_ok!(Kitties::create_kitty(Origin::signed(ALICE)));
+
+		assert_ok!(Kitties::set_prices_batch(Origin::signed(ALICE), vec![(0, 100), (2, 200)]));
+
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, Some(100));
+		assert_eq!(Kitties::kitties(2).expect("should found the kitty").price, Some(200));
+		System::assert_has_event(Event::Kitties(crate::Event::PriceSet(
+			ALICE,
+			2,
+			Some(200),
+			PriceChange::Listed,
+		)));
+		System::assert_last_event(Event::Kitties(crate::Event::BatchCompleted(
+			ALICE,
+			BatchOperation::SetPrice,
+			2,
+		)));
+
+		assert_noop!(
+			Kitties::set_prices_batch(Origin::signed(ALICE), vec![(0, 300), (1, 300)]),
+			Error::<Test>::NotKittyOwner
+		);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, Some(100));
+	});
+}
+
+fn permit_signer(seed: u8) -> (sr25519::Pair, AccountId) {
+	let pair = sr25519::Pair::from_seed(&[seed; 32]);
+	let account = AccountId::from(pair.public());
+	(pair, account)
+}
+
+fn permit_call(
+	pair: &sr25519::Pair,
+	from: AccountId,
+	kitty_id: u64,
+	deadline: u64,
+	nonce: u32,
+) -> crate::Call<Test> {
+	let payload = Kitties::permit_payload(&from, &BOB, &kitty_id, deadline, nonce);
+	crate::Call::transfer_with_permit {
+		from,
+		to: BOB,
+		kitty_id,
+		deadline,
+		nonce,
+		signature: MultiSignature::from(pair.sign(&payload)),
+	}
+}
+
+fn dispatch_unsigned(call: crate::Call<Test>) -> DispatchResult {
+	call.dispatch_bypass_filter(Origin::none()).map(|_| ()).map_err(|e| e.error)
+}
+
+#[test]
+fn transfer_with_permit_should_work() {
+	new_test_ext().execute_with(|| {
+		// the owner holds no balance at all
+		let (pair, owner) = permit_signer(7);
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::create_kitty(Origin::signed(owner.clone())));
+
+		let call = permit_call(&pair, owner.clone(), 2, 10, 0);
+		assert_ok!(Kitties::validate_unsigned(TransactionSource::External, &call));
+		assert_ok!(dispatch_unsigned(call.clone()));
+
+		assert_eq!(Kitties::kitties(2).expect("should found the kitty").owner, BOB);
+		assert_eq!(Kitties::permit_nonce(&owner), 1);
+		System::assert_has_event(Event::Kitties(crate::Event::Transferred(owner, BOB, 2)));
+
+		// the permit cannot be replayed
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Stale.into()
+		);
+		assert_noop!(dispatch_unsigned(call), Error::<Test>::InvalidPermitNonce);
+	});
+}
+
+#[test]
+fn transfer_with_permit_error_by_kitty_locked() {
+	new_test_ext().execute_with(|| {
+		let (pair, owner) = permit_signer(7);
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::create_kitty(Origin::signed(owner.clone())));
+		let call = permit_call(&pair, owner, 2, 10, 0);
+
+		// a permit failing at dispatch is not valid in the pool either
+		assert_ok!(Kitties::freeze_kitty(Origin::root(), 2));
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Call.into()
+		);
+		assert_noop!(dispatch_unsigned(call.clone()), Error::<Test>::KittyFrozen);
+
+		assert_ok!(Kitties::thaw_kitty(Origin::root(), 2));
+		assert_ok!(Kitties::blacklist_account(Origin::root(), BOB));
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Call.into()
+		);
+		assert_noop!(dispatch_unsigned(call), Error::<Test>::AccountBlacklisted);
+	});
+}
+
+#[test]
+fn transfer_with_permit_error_by_invalid_signature() {
+	new_test_ext().execute_with(|| {
+		let (_, owner) = permit_signer(7);
+		let (mallory, _) = permit_signer(8);
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::create_kitty(Origin::signed(owner.clone())));
+
+		let call = permit_call(&mallory, owner, 2, 10, 0);
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::BadProof.into()
+		);
+		assert_noop!(dispatch_unsigned(call), Error::<Test>::InvalidPermitSignature);
+	});
+}
+
+#[test]
+fn transfer_with_permit_error_by_permit_expired() {
+	new_test_ext().execute_with(|| {
+		let (pair, owner) = permit_signer(7);
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::create_kitty(Origin::signed(owner.clone())));
+
+		let call = permit_call(&pair, owner.clone(), 2, 10, 0);
+		System::set_block_number(11);
+		assert_noop!(dispatch_unsigned(call), Error::<Test>::PermitExpired);
+
+		// a permit from the future is not valid yet
+		let call = permit_call(&pair, owner, 2, 20, 1);
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Future.into()
+		);
+	});
+}
+
+fn gift_claim_call(
+	pair: &sr25519::Pair,
+	kitty_id: u64,
+	recipient: AccountId,
+	expiry: u64,
+) -> crate::Call<Test> {
+	let gift_key = AccountId::from(pair.public());
+	let payload = Kitties::gift_claim_payload(&gift_key, &kitty_id, &recipient, expiry);
+	crate::Call::claim_gift {
+		gift_key,
+		recipient,
+		signature: MultiSignature::from(pair.sign(&payload)),
+	}
+}
+
+#[test]
+fn claim_gift_should_work() {
+	new_test_ext().execute_with(|| {
+		let (pair, gift_key) = permit_signer(7);
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key, 10));
+
+		// the gifted kitty is locked and off the market
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, None);
+		assert_noop!(
+			Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None),
+			Error::<Test>::KittyInGift
+		);
+
+		// COCO holds no balance at all
+		let call = gift_claim_call(&pair, 0, COCO, 10);
+		assert_ok!(Kitties::validate_unsigned(TransactionSource::External, &call));
+		assert_ok!(dispatch_unsigned(call.clone()));
+
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, COCO);
+		assert_eq!(Balances::reserved_balance(COCO), 5000);
+		assert_eq!(Kitties::kitty_gift(0), None);
+		assert!(Kitties::gifts_expiring(10).is_empty());
+		System::assert_has_event(Event::Kitties(crate::Event::GiftClaimed(ALICE, COCO, 0)));
+
+		assert_noop!(dispatch_unsigned(call), Error::<Test>::GiftNotExist);
+	});
+}
+
+#[test]
+fn claim_gift_error_by_redirected_recipient() {
+	new_test_ext().execute_with(|| {
+		let (pair, gift_key) = permit_signer(7);
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key, 10));
+
+		// a claim seen in the pool cannot be sent to another recipient
+		let call = match gift_claim_call(&pair, 0, COCO, 10) {
+			crate::Call::claim_gift { gift_key, signature, .. } =>
+				crate::Call::claim_gift { gift_key, recipient: BOB, signature },
+			_ => unreachable!(),
+		};
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::BadProof.into()
+		);
+		assert_noop!(dispatch_unsigned(call), Error::<Test>::InvalidGiftSignature);
+	});
+}
+
+#[test]
+fn claim_gift_should_be_rate_limited() {
+	new_test_ext().execute_with(|| {
+		let ((pair1, key1), (pair2, key2)) = (permit_signer(7), permit_signer(8));
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, key1, 10));
+		assert_ok!(Kitties::create_gift(Origin::signed(BOB), 1, key2, 10));
+		assert_ok!(dispatch_unsigned(gift_claim_call(&pair1, 0, COCO, 10)));
+
+		let call = gift_claim_call(&pair2, 1, COCO, 10);
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::ExhaustsResources.into()
+		);
+		assert_noop!(dispatch_unsigned(call.clone()), Error::<Test>::TooManyGiftClaims);
+
+		System::set_block_number(2);
+		assert_ok!(dispatch_unsigned(call));
+	});
+}
+
+#[test]
+fn gift_should_return_to_sender_after_expiry() {
+	new_test_ext().execute_with(|| {
+		let (pair, gift_key) = permit_signer(7);
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key, 5));
+
+		run_to_block(5);
+
+		System::assert_has_event(Event::Kitties(crate::Event::GiftExpired(ALICE, 0)));
+		assert_eq!(Kitties::kitty_gift(0), None);
+		assert_noop!(
+			dispatch_unsigned(gift_claim_call(&pair, 0, COCO, 5)),
+			Error::<Test>::GiftNotExist
+		);
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
+	});
+}
+
+#[test]
+fn freeze_kitty_should_cancel_gift() {
+	new_test_ext().execute_with(|| {
+		let (pair, gift_key) = permit_signer(7);
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key.clone(), 10));
+		assert_ok!(Kitties::freeze_kitty(Origin::root(), 0));
+
+		System::assert_has_event(Event::Kitties(crate::Event::GiftCancelled(ALICE, 0)));
+		assert_eq!(Kitties::kitty_gift(0), None);
+		assert_eq!(Kitties::gifts(gift_key), None);
+		assert!(Kitties::gifts_expiring(10).is_empty());
+		assert_noop!(
+			dispatch_unsigned(gift_claim_call(&pair, 0, COCO, 10)),
+			Error::<Test>::GiftNotExist
+		);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, ALICE);
+	});
+}
+
+#[test]
+fn orphaned_kitty_should_cancel_gift() {
+	new_test_ext().execute_with(|| {
+		let (pair, gift_key) = permit_signer(7);
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::reprice_deposit(Origin::signed(ALICE), 0));
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key.clone(), 10));
+		assert_ok!(Balances::transfer(Origin::signed(ALICE), BOB, 10000));
+		run_to_block(2);
+
+		System::assert_has_event(Event::Kitties(crate::Event::GiftCancelled(ALICE, 0)));
+		assert_eq!(Kitties::kitty_gift(0), None);
+		assert_eq!(Kitties::gifts(gift_key), None);
+		assert!(Kitties::gifts_expiring(10).is_empty());
+
+		// the old gift key cannot take the kitty away from its adopter
+		assert_ok!(Kitties::adopt_kitty(Origin::signed(BOB), 0));
+		assert_noop!(
+			dispatch_unsigned(gift_claim_call(&pair, 0, COCO, 10)),
+			Error::<Test>::GiftNotExist
+		);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, BOB);
+	});
+}
+
+#[test]
+fn claim_gift_error_by_sender_blacklisted() {
+	new_test_ext().execute_with(|| {
+		let (pair, gift_key) = permit_signer(7);
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key, 10));
+		assert_ok!(Kitties::blacklist_account(Origin::root(), ALICE));
+
+		let call = gift_claim_call(&pair, 0, COCO, 10);
+		assert_eq!(
+			Kitties::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Call.into()
+		);
+		assert_noop!(dispatch_unsigned(call), Error::<Test>::AccountBlacklisted);
+	});
+}
+
+#[test]
+fn claim_gift_should_provide_gift_key() {
+	new_test_ext().execute_with(|| {
+		let (pair, gift_key) = permit_signer(7);
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key.clone(), 10));
+
+		// claims of the same gift for different recipients replace each other in the pool
+		let to_coco = Kitties::validate_unsigned(
+			TransactionSource::External,
+			&gift_claim_call(&pair, 0, COCO, 10),
+		);
+		let to_bob = Kitties::validate_unsigned(
+			TransactionSource::External,
+			&gift_claim_call(&pair, 0, BOB, 10),
+		);
+		let provides = to_coco.expect("the claim should be valid").provides;
+		assert_eq!(provides, to_bob.expect("the claim should be valid").provides);
+		assert_eq!(provides, vec![("KittiesGift", gift_key).encode()]);
+	});
+}
+
+#[test]
+fn create_gift_error() {
+	new_test_ext().execute_with(|| {
+		let (_, gift_key) = permit_signer(7);
+		assert_noop!(
+			Kitties::create_gift(Origin::signed(ALICE), 1, gift_key.clone(), 10),
+			Error::<Test>::NotKittyOwner
+		);
+		assert_noop!(
+			Kitties::create_gift(Origin::signed(ALICE), 0, gift_key.clone(), 1),
+			Error::<Test>::GiftExpiryInPast
+		);
+
+		assert_ok!(Kitties::create_gift(Origin::signed(ALICE), 0, gift_key.clone(), 10));
+		assert_noop!(
+			Kitties::create_gift(Origin::signed(BOB), 1, gift_key, 10),
+			Error::<Test>::GiftExists
+		);
+	});
+}
+
+#[test]
+fn kitty_hooks_should_be_called() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::set_pledge(Origin::root(), 0));
+		assert_ok!(Kitties::create_kitty(Origin::signed(ALICE)));
+		assert_ok!(Kitties::breed_kitty(Origin::signed(ALICE), 0, 2));
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 3));
+		assert_ok!(Kitties::burn_kitty(Origin::signed(BOB), 3));
+
+		assert_eq!(
+			hook_calls(),
+			vec![
+				HookCall::Minted(ALICE, 0),
+				HookCall::Minted(BOB, 1),
+				HookCall::Minted(ALICE, 2),
+				HookCall::Minted(ALICE, 3),
+				HookCall::Bred(ALICE, (0, 2), 3),
+				HookCall::Transferred(ALICE, BOB, 3),
+				HookCall::Burned(BOB, 3),
+			]
+		);
+	});
+}
+
+#[test]
+fn transfer_error_by_hook_veto() {
+	new_test_ext().execute_with(|| {
+		VETO_TRANSFERS.with(|veto| *veto.borrow_mut() = true);
+
+		assert_noop!(
+			Kitties::transfer(Origin::signed(ALICE), BOB, 0),
+			sp_runtime::DispatchError::Other("transfer vetoed")
+		);
+
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
+		assert_noop!(
+			Kitties::buy_kitty(Origin::signed(BOB), 0, 100, Kitties::listing_nonce(0)),
+			sp_runtime::DispatchError::Other("transfer vetoed")
+		);
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").owner, ALICE);
+	});
+}
+
+#[test]
+fn burn_kitty_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, Some(10)));
+		assert_ok!(Kitties::burn_kitty(Origin::signed(ALICE), 0));
+
+		assert_eq!(Kitties::kitties(0), None);
+		assert_eq!(Kitties::kitties_owned(0), None);
+		assert!(Kitties::owned_kitties(ALICE).is_empty());
+		assert_eq!(Kitties::listing_expiry(0), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(KittyCnt::<Test>::get(), 2);
+		System::assert_last_event(Event::Kitties(crate::Event::Burned(ALICE, 0)));
+
+		assert_noop!(Kitties::burn_kitty(Origin::signed(BOB), 0), Error::<Test>::KittyNotExist);
+		assert_noop!(Kitties::burn_kitty(Origin::signed(ALICE), 1), Error::<Test>::NotKittyOwner);
+	});
+}
+
+#[test]
+fn freeze_kitty_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None));
+		assert_noop!(
+			Kitties::freeze_kitty(Origin::signed(ALICE), 0),
+			sp_runtime::DispatchError::BadOrigin
+		);
+		assert_ok!(Kitties::freeze_kitty(Origin::root(), 0));
+
+		System::assert_last_event(Event::Kitties(crate::Event::KittyFrozen(0)));
+		assert_eq!(Kitties::kitties(0).expect("should found the kitty").price, None);
+		assert_noop!(Kitties::freeze_kitty(Origin::root(), 0), Error::<Test>::KittyFrozen);
+
+		// every path through exchange and breeding is closed
+		assert_noop!(
+			Kitties::sell_kitty(Origin::signed(ALICE), 0, 100, None),
+			Error::<Test>::KittyFrozen
+		);
+		assert_noop!(Kitties::transfer(Origin::signed(ALICE), BOB, 0), Error::<Test>::KittyFrozen);
+		assert_noop!(
+			Kitties::buy_kitty(Origin::signed(BOB), 0, 100, Kitties::listing_nonce(0)),
+			Error::<Test>::KittyFrozen
+		);
+		assert_ok!(Kitties::create_kitty(Origin::signed(ALICE)));
+		assert_noop!(Kitties::breed_kitty(Origin::signed(ALICE), 0, 2), Error::<Test>::KittyFrozen);
+
+		assert_ok!(Kitties::thaw_kitty(Origin::root(), 0));
+		System::assert_last_event(Event::Kitties(crate::Event::KittyThawed(0)));
+		assert_noop!(Kitties::thaw_kitty(Origin::root(), 0), Error::<Test>::KittyNotFrozen);
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+	});
+}
+
+#[test]
+fn force_transfer_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::freeze_kitty(Origin::root(), 0));
+		assert_ok!(Kitties::blacklist_account(Origin::root(), ALICE));
+
+		assert_ok!(Kitties::force_transfer(Origin::root(), 0, BOB));
+
+		let kitty = Kitties::kitties(0).expect("should found the kitty");
+		assert_eq!(kitty.owner, BOB);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::reserved_balance(BOB), 10000);
+		assert_eq!(Kitties::frozen_kitties(0), Some(()));
+		System::assert_last_event(Event::Kitties(crate::Event::ForceTransferred(ALICE, BOB, 0)));
+
+		assert_noop!(
+			Kitties::force_transfer(Origin::root(), 0, BOB),
+			Error::<Test>::TransferToSelf
+		);
+		assert_noop!(
+			Kitties::force_transfer(Origin::signed(BOB), 0, ALICE),
+			sp_runtime::DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn blacklist_account_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::sell_kitty(Origin::signed(BOB), 1, 100, None));
+		assert_ok!(Kitties::blacklist_account(Origin::root(), BOB));
+		System::assert_last_event(Event::Kitties(crate::Event::AccountBlacklisted(BOB)));
+		assert_noop!(
+			Kitties::blacklist_account(Origin::root(), BOB),
+			Error::<Test>::AccountBlacklisted
+		);
+
+		// BOB can neither sell, buy nor receive kitties
+		assert_noop!(
+			Kitties::buy_kitty(Origin::signed(ALICE), 1, 100, Kitties::listing_nonce(1)),
+			Error::<Test>::AccountBlacklisted
+		);
+		assert_noop!(
+			Kitties::transfer(Origin::signed(ALICE), BOB, 0),
+			Error::<Test>::AccountBlacklisted
+		);
+		assert_noop!(
+			Kitties::transfer(Origin::signed(BOB), ALICE, 1),
+			Error::<Test>::AccountBlacklisted
+		);
+
+		assert_ok!(Kitties::unblacklist_account(Origin::root(), BOB));
+		System::assert_last_event(Event::Kitties(crate::Event::AccountUnblacklisted(BOB)));
+		assert_noop!(
+			Kitties::unblacklist_account(Origin::root(), BOB),
+			Error::<Test>::AccountNotBlacklisted
+		);
+		assert_ok!(Kitties::transfer(Origin::signed(ALICE), BOB, 0));
+	});
+}
+
+#[test]
+fn try_state_should_detect_broken_invariants() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Kitties::do_try_state());
+
+		crate::pallet::KittiesOwned::<Test>::insert(0, BOB);
+		assert_eq!(
+			Kitties::do_try_state(),
+			Err("KittiesOwned does not match the owner of a Kitty")
+		);
+		crate::pallet::KittiesOwned::<Test>::insert(0, ALICE);
+
+		Balances::unreserve(&ALICE, 1);
+		assert_eq!(
+			Kitties::do_try_state(),
+			Err("Reserved balance does not cover the Kitty deposits")
+		);
+		assert_ok!(Balances::reserve(&ALICE, 1));
+
+		crate::pallet::FrozenKitties::<Test>::insert(0, ());
+		crate::pallet::Kitties::<Test>::mutate(0, |k| {
+			if let Some(k) = k {
+				k.price = Some(100);
+			}
+		});
+		assert_eq!(Kitties::do_try_state(), Err("A frozen or locked Kitty has a price"));
+		crate::pallet::Kitties::<Test>::mutate(0, |k| {
+			if let Some(k) = k {
+				k.price = None;
+			}
+		});
+
+		KittyCnt::<Test>::put(1);
+		assert_eq!(Kitties::do_try_state(), Err("KittyCnt is lower than the number of kitties"));
+		KittyCnt::<Test>::put(2);
+	});
+}
@@ -0,0 +1,43 @@
+//! Hooks other pallets implement to react to the lifecycle of kitties.
+//!
+//! Each hook is implemented for `()` and for tuples, so a runtime can plug several pallets in.
+
+use frame_support::dispatch::DispatchResult;
+
+/// Called after a Kitty was minted, by creation, breeding or at genesis.
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnKittyMinted<AccountId, KittyIndex> {
+	fn on_kitty_minted(owner: &AccountId, kitty_id: &KittyIndex);
+}
+
+/// Called before a Kitty changes hands.
+///
+/// Returning an error vetoes the transfer, and the whole call fails.
+pub trait OnKittyTransferred<AccountId, KittyIndex> {
+	fn on_kitty_transferred(from: &AccountId, to: &AccountId, kitty_id: &KittyIndex)
+		-> DispatchResult;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl<AccountId, KittyIndex> OnKittyTransferred<AccountId, KittyIndex> for Tuple {
+	fn on_kitty_transferred(
+		from: &AccountId,
+		to: &AccountId,
+		kitty_id: &KittyIndex,
+	) -> DispatchResult {
+		for_tuples!( #( Tuple::on_kitty_transferred(from, to, kitty_id)?; )* );
+		Ok(())
+	}
+}
+
+/// Called after a Kitty was burned.
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnKittyBurned<AccountId, KittyIndex> {
+	fn on_kitty_burned(owner: &AccountId, kitty_id: &KittyIndex);
+}
+
+/// Called after two kitties bred a new one, right after its `OnKittyMinted`.
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+pub trait OnKittyBred<AccountId, KittyIndex> {
+	fn on_kitty_bred(owner: &AccountId, parents: (&KittyIndex, &KittyIndex), child: &KittyIndex);
+}
@@ -0,0 +1,67 @@
+//! RPC interface for the poe pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+pub use pallet_poe_runtime_api::PoeApi as PoeRuntimeApi;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+#[rpc]
+pub trait PoeApi<BlockHash> {
+	/// Whether `leaf` is the leaf at `index` of the batch anchored at `root`, `proof` holding
+	/// the siblings on its path bottom up.
+	#[rpc(name = "poe_verifyInclusion")]
+	fn verify_inclusion(
+		&self,
+		root: H256,
+		leaf: H256,
+		index: u32,
+		proof: Vec<H256>,
+		at: Option<BlockHash>,
+	) -> Result<bool>;
+}
+
+/// Serves `PoeApi` from the runtime of a client.
+pub struct Poe<C, Block, AccountId, BlockNumber> {
+	client: Arc<C>,
+	_marker: PhantomData<(Block, AccountId, BlockNumber)>,
+}
+
+impl<C, Block, AccountId, BlockNumber> Poe<C, Block, AccountId, BlockNumber> {
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+impl<C, Block, AccountId, BlockNumber> PoeApi<<Block as BlockT>::Hash>
+	for Poe<C, Block, AccountId, BlockNumber>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: PoeRuntimeApi<Block, AccountId, BlockNumber>,
+	AccountId: Codec + Send + Sync + 'static,
+	BlockNumber: Codec + Send + Sync + 'static,
+{
+	fn verify_inclusion(
+		&self,
+		root: H256,
+		leaf: H256,
+		index: u32,
+		proof: Vec<H256>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<bool> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.verify_inclusion(&at, root, leaf, index, proof).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(1),
+			message: "Unable to verify the inclusion proof.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
@@ -0,0 +1,28 @@
+//! Runtime API definition for the poe pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+pub use pallet_poe::{Custody, CustodyReason};
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	pub trait PoeApi<AccountId, BlockNumber> where
+		AccountId: Codec,
+		BlockNumber: Codec,
+	{
+		/// The chain of custody of a claim, oldest first.
+		fn provenance(claim: H256) -> Vec<Custody<AccountId, BlockNumber>>;
+
+		/// The owner of a claim at block `at`.
+		fn holder_at(claim: H256, at: BlockNumber) -> Option<AccountId>;
+
+		/// Whether `leaf` is the leaf at `index` of the batch anchored at `root`, `proof` holding
+		/// the siblings on its path bottom up.
+		fn verify_inclusion(root: H256, leaf: H256, index: u32, proof: Vec<H256>) -> bool;
+
+		/// Up to `limit` claims owned by `owner`, from the `start`th one, oldest first.
+		fn claims_of(owner: AccountId, start: u32, limit: u32) -> Vec<H256>;
+	}
+}
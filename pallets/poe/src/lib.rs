@@ -6,6 +6,8 @@
 pub use pallet::*;
 use sp_std::prelude::*;
 
+pub mod merkle;
+
 #[cfg(test)]
 mod mock;
 
@@ -14,21 +16,259 @@ mod tests;
 
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::{dispatch::DispatchResult, pallet_prelude::*, ensure};
+	use frame_support::{
+		dispatch::DispatchResult,
+		ensure,
+		pallet_prelude::*,
+		storage::migration::storage_key_iter,
+		traits::{
+			BalanceStatus, Currency, ExistenceRequirement, OnUnbalanced, PalletInfoAccess,
+			ReservableCurrency, StorageVersion, Time, WithdrawReasons,
+		},
+		transactional,
+	};
 	use frame_system::pallet_prelude::*;
-	use sp_std::prelude::*;
+	use scale_info::TypeInfo;
+	use sp_core::H256;
+	use sp_runtime::traits::{One, Saturating, UniqueSaturatedInto, Zero};
+	use sp_std::{convert::TryInto, prelude::*};
+
+	/// The algorithm a claim digest was computed with.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum HashAlgorithm {
+		Blake2_256,
+		Sha2_256,
+		Keccak256,
+	}
+
+	impl HashAlgorithm {
+		/// Hashes `data` with this algorithm.
+		pub fn hash(&self, data: &[u8]) -> H256 {
+			H256(match self {
+				HashAlgorithm::Blake2_256 => sp_io::hashing::blake2_256(data),
+				HashAlgorithm::Sha2_256 => sp_io::hashing::sha2_256(data),
+				HashAlgorithm::Keccak256 => sp_io::hashing::keccak_256(data),
+			})
+		}
+	}
+
+	/// An existing claim.
+	#[derive(Clone, Encode, Decode, PartialEq, RuntimeDebug, TypeInfo)]
+	pub struct Proof<AccountId, BlockNumber, Balance> {
+		pub owner: AccountId,
+		pub block_number: BlockNumber,
+		/// The `ClaimHashing` in force when the claim was made. A submitted digest is not
+		/// checked against it, only claims from a preimage are hashed with it.
+		pub algorithm: HashAlgorithm,
+		/// Reserved from the owner while the claim exists.
+		pub deposit: Balance,
+	}
+
+	/// The metadata of a claim, as submitted by its owner.
+	#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct ClaimMetadata {
+		pub title: Vec<u8>,
+		/// The MIME type of the document, e.g. `application/pdf`.
+		pub content_type: Vec<u8>,
+		/// The length of the document in bytes.
+		pub content_length: u64,
+		/// Where the document can be fetched from, e.g. an IPFS CID.
+		pub uri: Option<Vec<u8>>,
+	}
+
+	/// The metadata stored for a claim.
+	#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct ClaimInfo<T: Config> {
+		pub title: BoundedVec<u8, T::MaxTitleLength>,
+		pub content_type: BoundedVec<u8, T::MaxContentTypeLength>,
+		pub content_length: u64,
+		pub uri: Option<BoundedVec<u8, T::MaxUriLength>>,
+		/// The timestamp the claim was created at.
+		pub created_at: MomentOf<T>,
+		/// Starts at 1 and is bumped on every update.
+		pub version: u32,
+	}
+
+	/// How an owner came to hold a claim.
+	#[derive(Clone, Copy, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub enum CustodyReason {
+		Created,
+		Transferred,
+	}
+
+	/// A period a claim was held by one owner.
+	#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct Custody<AccountId, BlockNumber> {
+		pub owner: AccountId,
+		pub from_block: BlockNumber,
+		/// The block the custody ended at, `None` while the owner still holds the claim.
+		pub to_block: Option<BlockNumber>,
+		pub reason: CustodyReason,
+	}
+
+	/// A pending transfer of a claim, waiting for `dest` to accept it.
+	#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct Offer<AccountId, BlockNumber> {
+		pub dest: AccountId,
+		/// The block the offer is dropped at.
+		pub expiry: BlockNumber,
+	}
+
+	/// A claim waiting for `threshold` of its `signers` to approve it.
+	#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	#[scale_info(skip_type_params(T))]
+	pub struct PendingClaim<T: Config> {
+		pub proposer: T::AccountId,
+		pub signers: BoundedVec<T::AccountId, T::MaxSigners>,
+		pub threshold: u32,
+		pub approvals: BoundedVec<T::AccountId, T::MaxSigners>,
+		pub info: ClaimInfo<T>,
+		/// Reserved from the proposer, it becomes the deposit of the claim.
+		pub deposit: BalanceOf<T>,
+		/// The block the pending claim is dropped at.
+		pub expiry: T::BlockNumber,
+	}
+
+	/// A statement another account attached to a claim.
+	#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+	pub struct Endorsement<AccountId, BlockNumber, Balance> {
+		pub endorser: AccountId,
+		/// The digest of the endorsed statement.
+		pub statement: H256,
+		pub block_number: BlockNumber,
+		/// Whether the endorser was a trusted attestor when endorsing. `Pallet::endorsements`
+		/// clears it once the endorser is no longer one.
+		pub verified: bool,
+		/// Reserved from the endorser while the endorsement exists.
+		pub deposit: Balance,
+	}
+
+	pub type BalanceOf<T> =
+		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
+	pub type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<
+		<T as frame_system::Config>::AccountId,
+	>>::NegativeImbalance;
+
+	pub type ProofOf<T> = Proof<
+		<T as frame_system::Config>::AccountId,
+		<T as frame_system::Config>::BlockNumber,
+		BalanceOf<T>,
+	>;
+
+	pub type CustodyOf<T> =
+		Custody<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+	pub type OfferOf<T> =
+		Offer<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+	pub type EndorsementOf<T> = Endorsement<
+		<T as frame_system::Config>::AccountId,
+		<T as frame_system::Config>::BlockNumber,
+		BalanceOf<T>,
+	>;
+
+	pub type MomentOf<T> = <<T as Config>::Time as Time>::Moment;
+
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
 	pub trait Config: frame_system::Config {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
 
+		/// The maximum length of a preimage hashed by `create_claim_from_preimage`.
 		#[pallet::constant]
 		type Length: Get<u32>;
+
+		/// The algorithm new claim digests are expected to be computed with, recorded in each
+		/// claim. `create_claim` cannot check a digest against it.
+		#[pallet::constant]
+		type ClaimHashing: Get<HashAlgorithm>;
+
+		/// The currency claim deposits are reserved in.
+		type Currency: ReservableCurrency<Self::AccountId>;
+
+		/// The deposit reserved for every claim.
+		#[pallet::constant]
+		type ClaimDepositBase: Get<BalanceOf<Self>>;
+
+		/// The deposit reserved for every byte a claim takes in storage.
+		#[pallet::constant]
+		type ClaimDepositPerByte: Get<BalanceOf<Self>>;
+
+		/// The source of the claim creation timestamps, usually `pallet_timestamp`.
+		type Time: Time;
+
+		/// The maximum length of a claim title.
+		#[pallet::constant]
+		type MaxTitleLength: Get<u32>;
+
+		/// The maximum length of a claim content type.
+		#[pallet::constant]
+		type MaxContentTypeLength: Get<u32>;
+
+		/// The maximum length of a claim URI.
+		#[pallet::constant]
+		type MaxUriLength: Get<u32>;
+
+		/// The maximum number of custody entries kept per claim, the oldest are dropped first.
+		#[pallet::constant]
+		type MaxHistoryLength: Get<u32>;
+
+		/// The number of blocks a transfer offer stays open.
+		#[pallet::constant]
+		type OfferPeriod: Get<Self::BlockNumber>;
+
+		/// The maximum number of transfer offers expiring in a block.
+		#[pallet::constant]
+		type MaxOffersExpiringPerBlock: Get<u32>;
+
+		/// The maximum number of signers of a co-attested claim.
+		#[pallet::constant]
+		type MaxSigners: Get<u32>;
+
+		/// The number of blocks a co-attested claim can wait for approvals.
+		#[pallet::constant]
+		type PendingClaimPeriod: Get<Self::BlockNumber>;
+
+		/// The maximum number of pending claims expiring in a block.
+		#[pallet::constant]
+		type MaxPendingExpiringPerBlock: Get<u32>;
+
+		/// The maximum number of endorsements of a claim.
+		#[pallet::constant]
+		type MaxEndorsements: Get<u32>;
+
+		/// The deposit reserved for every endorsement.
+		#[pallet::constant]
+		type EndorsementDeposit: Get<BalanceOf<Self>>;
+
+		/// The origin managing the trusted attestors.
+		type AttestorOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The maximum number of claims expiring in a block.
+		#[pallet::constant]
+		type MaxClaimsExpiringPerBlock: Get<u32>;
+
+		/// The fee for every block `renew_claim` extends a claim by.
+		#[pallet::constant]
+		type RenewalFeePerBlock: Get<BalanceOf<Self>>;
+
+		/// Handler for the renewal fees, they are burned with `()`.
+		type OnRenewalFee: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+		/// The maximum number of claims an account can own.
+		#[pallet::constant]
+		type MaxClaimsPerAccount: Get<u32>;
 	}
 
+	/// The storage version, bumped when `Proofs` moved from preimage keys to digests.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
 	#[pallet::pallet]
 	#[pallet::generate_store(pub(super) trait Store)]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	// The pallet's runtime storage items.
@@ -45,11 +285,126 @@ pub mod pallet {
 	pub(super) type Proofs<T: Config> = StorageMap<
 		_,
 		Blake2_128Concat,
-		Vec<u8>,
-		(T::AccountId,T::BlockNumber),
+		H256,
+		ProofOf<T>,
 		OptionQuery,
 	>;
 
+	#[pallet::storage]
+	#[pallet::getter(fn claim_info)]
+	pub(super) type ClaimInfos<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		H256,
+		ClaimInfo<T>,
+		OptionQuery,
+	>;
+
+	/// The claims owned by an account, oldest first.
+	#[pallet::storage]
+	#[pallet::getter(fn owned_claims)]
+	pub(super) type ClaimsOf<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		T::AccountId,
+		BoundedVec<H256, T::MaxClaimsPerAccount>,
+		ValueQuery,
+	>;
+
+	/// The chain of custody of every claim, kept after the claim is revoked.
+	#[pallet::storage]
+	#[pallet::getter(fn history)]
+	pub(super) type History<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		H256,
+		BoundedVec<CustodyOf<T>, T::MaxHistoryLength>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn offers)]
+	pub(super) type Offers<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		H256,
+		OfferOf<T>,
+		OptionQuery,
+	>;
+
+	/// The offers dropped at the start of a block.
+	#[pallet::storage]
+	pub(super) type OffersExpiring<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<H256, T::MaxOffersExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	#[pallet::getter(fn pending_claims)]
+	pub(super) type PendingClaims<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		H256,
+		PendingClaim<T>,
+		OptionQuery,
+	>;
+
+	/// The pending claims dropped at the start of a block.
+	#[pallet::storage]
+	pub(super) type PendingExpiring<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<H256, T::MaxPendingExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	/// Read through `Pallet::endorsements`, which re-checks `verified` against the attestors.
+	#[pallet::storage]
+	pub(super) type Endorsements<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		H256,
+		BoundedVec<EndorsementOf<T>, T::MaxEndorsements>,
+		ValueQuery,
+	>;
+
+	/// The block at which a claim expires, claims without one live forever.
+	#[pallet::storage]
+	#[pallet::getter(fn claim_expiry)]
+	pub(super) type ClaimExpiry<T: Config> =
+		StorageMap<_, Blake2_128Concat, H256, T::BlockNumber, OptionQuery>;
+
+	/// The claims expiring at a block, swept by `on_idle`.
+	#[pallet::storage]
+	pub(super) type ClaimsExpiring<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<H256, T::MaxClaimsExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	/// The first block whose expiring claims were not swept yet, set by the first claim with
+	/// an expiry.
+	#[pallet::storage]
+	#[pallet::getter(fn next_claim_expiry_block)]
+	pub(super) type NextClaimExpiryBlock<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+	/// The number of leaves of the Merkle roots anchored with `anchor_batch`.
+	#[pallet::storage]
+	#[pallet::getter(fn batch_leaf_count)]
+	pub(super) type Batches<T: Config> = StorageMap<_, Blake2_128Concat, H256, u32, OptionQuery>;
+
+	/// The accounts whose endorsements are marked as verified.
+	#[pallet::storage]
+	#[pallet::getter(fn attestors)]
+	pub(super) type Attestors<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, (), OptionQuery>;
+
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/v3/runtime/events-and-errors
 	#[pallet::event]
@@ -57,20 +412,115 @@ pub mod pallet {
 	pub enum Event<T: Config> {
 		/// ClaimCreate
 		/// - id T::AccountId 来源
-		/// - claim Vec<u8> 存证明
-		ClaimCreate( T::AccountId,Vec<u8>),
+		/// - claim H256 存证明
+		/// - algorithm HashAlgorithm 摘要算法
+		/// - deposit BalanceOf<T> 押金
+		ClaimCreate( T::AccountId,H256,HashAlgorithm,BalanceOf<T>),
 
 
 		/// ClaimTrans
 		/// - id T::AccountId 来源
 		/// - dest T::AccountId 转义目标
-		/// - claim Vec<u8> 存证明
-		ClaimTrans( T::AccountId,T::AccountId,Vec<u8>),
+		/// - claim H256 存证明
+		/// - deposit BalanceOf<T> 转给目标的押金
+		ClaimTrans( T::AccountId,T::AccountId,H256,BalanceOf<T>),
 
 		/// ClaimRevoked
 		/// - id T::AccountId 来源
-		/// - claim Vec<u8> 存证明
-		ClaimRevoked( T::AccountId,Vec<u8>),
+		/// - claim H256 存证明
+		/// - deposit BalanceOf<T> 退还的押金
+		ClaimRevoked( T::AccountId,H256,BalanceOf<T>),
+
+		/// ClaimInfoUpdated
+		/// - id T::AccountId 来源
+		/// - claim H256 存证明
+		/// - version u32 元数据版本
+		/// - deposit BalanceOf<T> 更新后的押金
+		ClaimInfoUpdated( T::AccountId,H256,u32,BalanceOf<T>),
+
+		/// ClaimOffered
+		/// - id T::AccountId 来源
+		/// - dest T::AccountId 转移目标
+		/// - claim H256 存证明
+		/// - expiry T::BlockNumber 过期区块
+		ClaimOffered( T::AccountId,T::AccountId,H256,T::BlockNumber),
+
+		/// OfferRejected
+		/// - dest T::AccountId 拒绝的目标
+		/// - claim H256 存证明
+		OfferRejected( T::AccountId,H256),
+
+		/// OfferCancelled
+		/// - id T::AccountId 来源
+		/// - claim H256 存证明
+		OfferCancelled( T::AccountId,H256),
+
+		/// OfferExpired
+		/// - claim H256 存证明
+		OfferExpired( H256),
+
+		/// ClaimProposed
+		/// - id T::AccountId 提议人
+		/// - claim H256 存证明
+		/// - threshold u32 需要的签名数
+		/// - expiry T::BlockNumber 过期区块
+		ClaimProposed( T::AccountId,H256,u32,T::BlockNumber),
+
+		/// ClaimApproved
+		/// - id T::AccountId 签名人
+		/// - claim H256 存证明
+		/// - approvals u32 已有的签名数
+		ClaimApproved( T::AccountId,H256,u32),
+
+		/// PendingClaimExpired
+		/// - claim H256 存证明
+		/// - deposit BalanceOf<T> 退还给提议人的押金
+		PendingClaimExpired( H256,BalanceOf<T>),
+
+		/// ClaimEndorsed
+		/// - id T::AccountId 背书人
+		/// - claim H256 存证明
+		/// - statement H256 背书内容摘要
+		/// - verified bool 是否为可信证明人
+		ClaimEndorsed( T::AccountId,H256,H256,bool),
+
+		/// EndorsementRevoked
+		/// - id T::AccountId 背书人
+		/// - claim H256 存证明
+		EndorsementRevoked( T::AccountId,H256),
+
+		/// AttestorAdded
+		/// - id T::AccountId 可信证明人
+		AttestorAdded( T::AccountId),
+
+		/// AttestorRemoved
+		/// - id T::AccountId 可信证明人
+		AttestorRemoved( T::AccountId),
+
+		/// ClaimRenewed
+		/// - id T::AccountId 来源
+		/// - claim H256 存证明
+		/// - expiry T::BlockNumber 新的过期区块
+		/// - fee BalanceOf<T> 续期费用
+		ClaimRenewed( T::AccountId,H256,T::BlockNumber,BalanceOf<T>),
+
+		/// ClaimExpired
+		/// - id T::AccountId 所有者
+		/// - claim H256 存证明
+		/// - deposit BalanceOf<T> 退还的押金
+		ClaimExpired( T::AccountId,H256,BalanceOf<T>),
+
+		/// BatchAnchored
+		/// - id T::AccountId 来源
+		/// - root H256 默克尔根
+		/// - leaf_count u32 文档数量
+		BatchAnchored( T::AccountId,H256,u32),
+
+		/// EndorsementRemoved
+		/// - id T::AccountId 所有者
+		/// - endorser T::AccountId 背书人
+		/// - claim H256 存证明
+		EndorsementRemoved( T::AccountId,T::AccountId,H256),
 
 	}
 
@@ -85,6 +535,95 @@ pub mod pallet {
 		NotClaimOwner,
 
 		ClaimOverLength,
+
+		TitleTooLong,
+
+		ContentTypeTooLong,
+
+		UriTooLong,
+
+		OfferAlreadyExist,
+
+		OfferNotExist,
+
+		NotOfferRecipient,
+
+		CannotOfferToSelf,
+
+		TooManyOffersExpiring,
+
+		ClaimPending,
+
+		PendingClaimNotExist,
+
+		InvalidThreshold,
+
+		DuplicateSigner,
+
+		TooManySigners,
+
+		NotSigner,
+
+		AlreadyApproved,
+
+		TooManyPendingExpiring,
+
+		CannotEndorseOwnClaim,
+
+		AlreadyEndorsed,
+
+		EndorsementNotExist,
+
+		TooManyEndorsements,
+
+		AlreadyAttestor,
+
+		NotAttestor,
+
+		ClaimExpiryInPast,
+
+		TooManyClaimsExpiring,
+
+		ClaimNeverExpires,
+
+		ClaimExpired,
+
+		InvalidLeafCount,
+
+		TooManyClaims,
+
+		InvalidRenewalPeriod,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			let expiring = OffersExpiring::<T>::take(n);
+			let offers = expiring.len() as Weight;
+			for claim in expiring.into_inner() {
+				// the offer may have been answered or replaced since
+				if Offers::<T>::get(&claim).map_or(false, |offer| offer.expiry == n) {
+					Offers::<T>::remove(&claim);
+					Self::deposit_event(Event::OfferExpired(claim));
+				}
+			}
+
+			let expiring = PendingExpiring::<T>::take(n);
+			let pending = expiring.len() as Weight;
+			for claim in expiring.into_inner() {
+				Self::expire_pending_claim(&claim, n);
+			}
+
+			T::DbWeight::get().reads_writes(2 + offers + 2 * pending, 2 + offers + 2 * pending)
+		}
+
+		fn on_idle(n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			Self::sweep_expired_claims(n, remaining_weight)
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			Self::migrate_proofs()
+		}
 	}
 
 
@@ -92,63 +631,858 @@ pub mod pallet {
 	impl<T: Config> Pallet<T> {
 
 		/// storage and claim.
-		#[pallet::weight(0)]
-		pub fn create_claim(origin: OriginFor<T>, claim: Vec<u8>) -> DispatchResultWithPostInfo {
+		///
+		/// `claim` is the digest of the document, computed with `ClaimHashing`. A claim with
+		/// `expires_at` is removed once that block is reached, unless renewed.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 6))]
+		pub fn create_claim(
+			origin: OriginFor<T>,
+			claim: H256,
+			metadata: ClaimMetadata,
+			expires_at: Option<T::BlockNumber>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			Self::do_create_claim(who, claim, metadata, expires_at)?;
+
+			Ok(().into())
+		}
+
+		/// storage a claim of a small payload, hashed with `ClaimHashing` by the runtime.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 6))]
+		pub fn create_claim_from_preimage(
+			origin: OriginFor<T>,
+			preimage: Vec<u8>,
+			metadata: ClaimMetadata,
+			expires_at: Option<T::BlockNumber>,
+		) -> DispatchResultWithPostInfo {
 			let max_length = T::Length::get();
 
-			ensure!(claim.len()  <= max_length as usize , Error::<T>::ClaimOverLength);
+			ensure!(preimage.len()  <= max_length as usize , Error::<T>::ClaimOverLength);
 
 			let who = ensure_signed(origin)?;
-			//check proofs if it's exist.
-			ensure!(!Proofs::<T>::contains_key(&claim),Error::<T>::ProofAlreadyExist);
 
-			//insert the claim
-			Proofs::<T>::insert(&claim,(who.clone(),frame_system::Pallet::<T>::block_number()));
+			let claim = T::ClaimHashing::get().hash(&preimage);
+			Self::do_create_claim(who, claim, metadata, expires_at)?;
+
+			Ok(().into())
+		}
+
+
+
+		/// storage the Merkle root of a batch of `leaf_count` documents as a claim.
+		///
+		/// Every document can then be proven part of the batch with `merkle::verify_proof`.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 5))]
+		pub fn anchor_batch(
+			origin: OriginFor<T>,
+			root: H256,
+			leaf_count: u32,
+			metadata: ClaimMetadata,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			ensure!(leaf_count > 0,Error::<T>::InvalidLeafCount);
+
+			Self::do_create_claim(who.clone(), root, metadata, None)?;
+			Batches::<T>::insert(&root, leaf_count);
+
+			Self::deposit_event(Event::BatchAnchored(who, root, leaf_count));
 
-			Self::deposit_event(Event::ClaimCreate(who, claim));
+			Ok(().into())
+		}
+
+		/// propose a claim which is created once `threshold` of `signers` approved it.
+		///
+		/// The deposit is reserved from the proposer now, and refunded if the claim expires after
+		/// `PendingClaimPeriod` blocks.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn propose_claim(
+			origin: OriginFor<T>,
+			claim: H256,
+			signers: Vec<T::AccountId>,
+			threshold: u32,
+			metadata: ClaimMetadata,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			Self::ensure_claim_free(&claim)?;
+			// the claim is indexed under the proposer once approved
+			ensure!(
+				ClaimsOf::<T>::decode_len(&who).unwrap_or(0) <
+					T::MaxClaimsPerAccount::get() as usize,
+				Error::<T>::TooManyClaims
+			);
+
+			// bound the signers before sorting them
+			let signers: BoundedVec<_, T::MaxSigners> =
+				signers.try_into().map_err(|_| Error::<T>::TooManySigners)?;
+			let mut sorted = signers.clone().into_inner();
+			sorted.sort();
+			sorted.dedup();
+			ensure!(sorted.len() == signers.len(),Error::<T>::DuplicateSigner);
+			ensure!(
+				threshold > 0 && threshold as usize <= signers.len(),
+				Error::<T>::InvalidThreshold
+			);
+
+			let info = Self::claim_info_from(metadata, T::Time::now())?;
+			let deposit = Self::claim_deposit(&Self::new_proof(who.clone()), &info);
+			T::Currency::reserve(&who, deposit)?;
+
+			let expiry = frame_system::Pallet::<T>::block_number()
+				.saturating_add(T::PendingClaimPeriod::get());
+			PendingExpiring::<T>::try_mutate(&expiry, |claims| claims.try_push(claim))
+				.map_err(|_| Error::<T>::TooManyPendingExpiring)?;
+
+			PendingClaims::<T>::insert(
+				&claim,
+				PendingClaim {
+					proposer: who.clone(),
+					signers,
+					threshold,
+					approvals: Default::default(),
+					info,
+					deposit,
+					expiry,
+				},
+			);
+
+			Self::deposit_event(Event::ClaimProposed(who, claim, threshold, expiry));
 
 			Ok(().into())
 		}
 
+		/// approve a pending claim the sender is a signer of.
+		///
+		/// The approval reaching the threshold creates the claim, owned by the proposer.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 7))]
+		pub fn approve_claim(origin: OriginFor<T>, claim: H256) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			let mut pending =
+				PendingClaims::<T>::get(&claim).ok_or(Error::<T>::PendingClaimNotExist)?;
+			ensure!(pending.signers.contains(&who),Error::<T>::NotSigner);
+			ensure!(!pending.approvals.contains(&who),Error::<T>::AlreadyApproved);
 
+			// cannot fail, the approvals are a subset of the signers
+			pending.approvals.try_push(who.clone()).map_err(|_| Error::<T>::TooManySigners)?;
+			let approvals = pending.approvals.len() as u32;
 
-		#[pallet::weight(0)]
-		pub fn trans_claim(origin: OriginFor<T>, claim: Vec<u8> ,dest: T::AccountId) -> DispatchResultWithPostInfo {
+			Self::deposit_event(Event::ClaimApproved(who, claim, approvals));
 
+			if approvals >= pending.threshold {
+				PendingClaims::<T>::remove(&claim);
+				Self::remove_pending_expiring(&claim, pending.expiry);
+
+				let PendingClaim { proposer, mut info, deposit, .. } = pending;
+				info.created_at = T::Time::now();
+				let proof = Proof { deposit, ..Self::new_proof(proposer) };
+				Self::insert_claim(claim, proof, info)?;
+			} else {
+				PendingClaims::<T>::insert(&claim, pending);
+			}
+
+			Ok(().into())
+		}
+
+		/// offer a claim to `dest`, who takes it over with `accept_claim`.
+		///
+		/// The offer is dropped after `OfferPeriod` blocks.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn offer_claim(
+			origin: OriginFor<T>,
+			claim: H256,
+			dest: T::AccountId,
+		) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
 			//check proofs if it's exist.
-			let (owner,_) = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+			let proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
 
 			//check owner
-			ensure!(who == owner,Error::<T>::NotClaimOwner);
+			ensure!(who == proof.owner,Error::<T>::NotClaimOwner);
+			ensure!(who != dest,Error::<T>::CannotOfferToSelf);
+			ensure!(!Offers::<T>::contains_key(&claim),Error::<T>::OfferAlreadyExist);
 
-			//insert the claim
-			Proofs::<T>::insert(&claim,(dest.clone(),frame_system::Pallet::<T>::block_number()));
+			let expiry =
+				frame_system::Pallet::<T>::block_number().saturating_add(T::OfferPeriod::get());
+			OffersExpiring::<T>::try_mutate(&expiry, |claims| claims.try_push(claim))
+				.map_err(|_| Error::<T>::TooManyOffersExpiring)?;
+			Offers::<T>::insert(&claim, Offer { dest: dest.clone(), expiry });
+
+			Self::deposit_event(Event::ClaimOffered(who, dest, claim, expiry));
+
+			Ok(().into())
+		}
+
+		/// accept a claim offered to the sender, taking over its deposit.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(7, 7))]
+		pub fn accept_claim(origin: OriginFor<T>, claim: H256) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
 
-			Self::deposit_event(Event::ClaimTrans(who,dest, claim));
+			let offer = Offers::<T>::get(&claim).ok_or(Error::<T>::OfferNotExist)?;
+			ensure!(who == offer.dest,Error::<T>::NotOfferRecipient);
+
+			Self::remove_offer(&claim);
+			Self::do_trans_claim(claim, who)?;
 
 			Ok(().into())
 		}
 
+		/// reject a claim offered to the sender.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn reject_claim(origin: OriginFor<T>, claim: H256) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			let offer = Offers::<T>::get(&claim).ok_or(Error::<T>::OfferNotExist)?;
+			ensure!(who == offer.dest,Error::<T>::NotOfferRecipient);
 
-		#[pallet::weight(0)]
-		pub fn revoked_claim(origin: OriginFor<T>, claim: Vec<u8>) -> DispatchResultWithPostInfo {
+			Self::remove_offer(&claim);
+
+			Self::deposit_event(Event::OfferRejected(who, claim));
+
+			Ok(().into())
+		}
 
+		/// withdraw the pending offer of a claim.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 1))]
+		pub fn cancel_offer(origin: OriginFor<T>, claim: H256) -> DispatchResultWithPostInfo {
 			let who = ensure_signed(origin)?;
 
 			//check proofs if it's exist.
-			let (owner,_) = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+			let proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
 
 			//check owner
-			ensure!(who == owner,Error::<T>::NotClaimOwner);
+			ensure!(who == proof.owner,Error::<T>::NotClaimOwner);
+			ensure!(Offers::<T>::contains_key(&claim),Error::<T>::OfferNotExist);
+
+			Self::remove_offer(&claim);
+
+			Self::deposit_event(Event::OfferCancelled(who, claim));
+
+			Ok(().into())
+		}
+
+		#[pallet::weight(
+			10_000 + T::DbWeight::get().reads_writes(4, 9) +
+				T::DbWeight::get().reads_writes(1, 1) * T::MaxEndorsements::get() as Weight
+		)]
+		pub fn revoked_claim(origin: OriginFor<T>, claim: H256) -> DispatchResultWithPostInfo {
+
+			let who = ensure_signed(origin)?;
+
+			//check proofs if it's exist.
+			let proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+
+			//check owner
+			ensure!(who == proof.owner,Error::<T>::NotClaimOwner);
 
 			//remove the key name claim
-			Proofs::<T>::remove(&claim);
+			Self::remove_claim(&claim, &proof);
+
+			Self::deposit_event(Event::ClaimRevoked(who, claim, proof.deposit));
+
+			Ok(().into())
+		}
+
+		/// replace the metadata of a claim, bumping its version.
+		///
+		/// The deposit is topped up or partly refunded to match the new size.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 3))]
+		pub fn update_claim_info(
+			origin: OriginFor<T>,
+			claim: H256,
+			metadata: ClaimMetadata,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			//check proofs if it's exist.
+			let mut proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+
+			//check owner
+			ensure!(who == proof.owner,Error::<T>::NotClaimOwner);
+
+			let old = ClaimInfos::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+			let mut info = Self::claim_info_from(metadata, old.created_at)?;
+			info.version = old.version.saturating_add(1);
+
+			//adjust the deposit to the new size
+			let deposit = Self::claim_deposit(&proof, &info);
+			if deposit > proof.deposit {
+				T::Currency::reserve(&who, deposit - proof.deposit)?;
+			} else {
+				T::Currency::unreserve(&who, proof.deposit - deposit);
+			}
+			proof.deposit = deposit;
+
+			let version = info.version;
+			Proofs::<T>::insert(&claim, proof);
+			ClaimInfos::<T>::insert(&claim, info);
+
+			Self::deposit_event(Event::ClaimInfoUpdated(who, claim, version, deposit));
 
 			Ok(().into())
 		}
 
+		/// extend a claim with an expiry by `blocks`, paying `RenewalFeePerBlock` for each.
+		#[transactional]
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4, 4))]
+		pub fn renew_claim(
+			origin: OriginFor<T>,
+			claim: H256,
+			blocks: T::BlockNumber,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(!blocks.is_zero(),Error::<T>::InvalidRenewalPeriod);
+
+			//check proofs if it's exist.
+			let proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+
+			//check owner
+			ensure!(who == proof.owner,Error::<T>::NotClaimOwner);
+
+			let expiry = ClaimExpiry::<T>::get(&claim).ok_or(Error::<T>::ClaimNeverExpires)?;
+			ensure!(frame_system::Pallet::<T>::block_number() < expiry,Error::<T>::ClaimExpired);
+
+			let blocks_count: u32 = blocks.unique_saturated_into();
+			let fee = T::RenewalFeePerBlock::get().saturating_mul(blocks_count.into());
+			let imbalance = T::Currency::withdraw(
+				&who,
+				fee,
+				WithdrawReasons::FEE,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			T::OnRenewalFee::on_unbalanced(imbalance);
+
+			let expiry = expiry.saturating_add(blocks);
+			Self::set_claim_expiry(&claim, expiry)?;
+
+			Self::deposit_event(Event::ClaimRenewed(who, claim, expiry, fee));
+
+			Ok(().into())
+		}
+
+		/// endorse a claim of another account with the digest of a statement.
+		///
+		/// `EndorsementDeposit` is reserved until the endorsement or the claim is revoked.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn endorse(
+			origin: OriginFor<T>,
+			claim: H256,
+			statement_hash: H256,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			//check proofs if it's exist.
+			let proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+			ensure!(who != proof.owner,Error::<T>::CannotEndorseOwnClaim);
+
+			let mut endorsements = Endorsements::<T>::get(&claim);
+			ensure!(
+				!endorsements.iter().any(|endorsement| endorsement.endorser == who),
+				Error::<T>::AlreadyEndorsed
+			);
+
+			let verified = Attestors::<T>::contains_key(&who);
+			let deposit = T::EndorsementDeposit::get();
+			endorsements
+				.try_push(Endorsement {
+					endorser: who.clone(),
+					statement: statement_hash,
+					block_number: frame_system::Pallet::<T>::block_number(),
+					verified,
+					deposit,
+				})
+				.map_err(|_| Error::<T>::TooManyEndorsements)?;
+			T::Currency::reserve(&who, deposit)?;
+			Endorsements::<T>::insert(&claim, endorsements);
+
+			Self::deposit_event(Event::ClaimEndorsed(who, claim, statement_hash, verified));
+
+			Ok(().into())
+		}
+
+		/// revoke the endorsement the sender attached to a claim.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(2, 2))]
+		pub fn revoke_endorsement(origin: OriginFor<T>, claim: H256) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			Self::take_endorsement(&claim, &who)?;
+
+			Self::deposit_event(Event::EndorsementRevoked(who, claim));
 
+			Ok(().into())
+		}
+
+		/// trust the endorsements of an account from now on.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn add_attestor(origin: OriginFor<T>, who: T::AccountId) -> DispatchResultWithPostInfo {
+			T::AttestorOrigin::ensure_origin(origin)?;
+			ensure!(!Attestors::<T>::contains_key(&who),Error::<T>::AlreadyAttestor);
+
+			Attestors::<T>::insert(&who, ());
+			Self::deposit_event(Event::AttestorAdded(who));
+
+			Ok(().into())
+		}
+
+		/// stop trusting the new endorsements of an account.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1, 1))]
+		pub fn remove_attestor(
+			origin: OriginFor<T>,
+			who: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			T::AttestorOrigin::ensure_origin(origin)?;
+			ensure!(Attestors::<T>::contains_key(&who),Error::<T>::NotAttestor);
+
+			Attestors::<T>::remove(&who);
+			Self::deposit_event(Event::AttestorRemoved(who));
+
+			Ok(().into())
+		}
+
+		/// remove an endorsement from a claim of the sender, refunding the endorser.
+		#[pallet::weight(10_000 + T::DbWeight::get().reads_writes(3, 2))]
+		pub fn remove_endorsement(
+			origin: OriginFor<T>,
+			claim: H256,
+			endorser: T::AccountId,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+
+			let proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+			ensure!(who == proof.owner,Error::<T>::NotClaimOwner);
+
+			Self::take_endorsement(&claim, &endorser)?;
+
+			Self::deposit_event(Event::EndorsementRemoved(who, endorser, claim));
+
+			Ok(().into())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		fn do_create_claim(
+			who: T::AccountId,
+			claim: H256,
+			metadata: ClaimMetadata,
+			expires_at: Option<T::BlockNumber>,
+		) -> DispatchResult {
+			Self::ensure_claim_free(&claim)?;
+			if let Some(expiry) = expires_at {
+				ensure!(
+					expiry > frame_system::Pallet::<T>::block_number(),
+					Error::<T>::ClaimExpiryInPast
+				);
+				Self::set_claim_expiry(&claim, expiry)?;
+			}
+
+			let info = Self::claim_info_from(metadata, T::Time::now())?;
+			let mut proof = Self::new_proof(who.clone());
+
+			//reserve the deposit
+			proof.deposit = Self::claim_deposit(&proof, &info);
+			T::Currency::reserve(&who, proof.deposit)?;
+
+			Self::insert_claim(claim, proof, info)
+		}
+
+		/// Checks `claim` is neither created nor pending.
+		fn ensure_claim_free(claim: &H256) -> DispatchResult {
+			//check proofs if it's exist.
+			ensure!(!Proofs::<T>::contains_key(claim),Error::<T>::ProofAlreadyExist);
+			ensure!(!PendingClaims::<T>::contains_key(claim),Error::<T>::ClaimPending);
+			Ok(())
+		}
+
+		/// A proof owned by `owner` from the current block, with no deposit yet.
+		fn new_proof(owner: T::AccountId) -> ProofOf<T> {
+			Proof {
+				owner,
+				block_number: frame_system::Pallet::<T>::block_number(),
+				algorithm: T::ClaimHashing::get(),
+				deposit: Zero::zero(),
+			}
+		}
+
+		/// Stores a claim whose deposit is already reserved.
+		fn insert_claim(claim: H256, proof: ProofOf<T>, info: ClaimInfo<T>) -> DispatchResult {
+			let (who, algorithm, deposit) = (proof.owner.clone(), proof.algorithm, proof.deposit);
+			Self::add_owned_claim(&who, claim)?;
+			Proofs::<T>::insert(&claim, proof);
+			ClaimInfos::<T>::insert(&claim, info);
+			Self::record_custody(&claim, Some((who.clone(), CustodyReason::Created)));
+
+			Self::deposit_event(Event::ClaimCreate(who, claim, algorithm, deposit));
+
+			Ok(())
+		}
+
+		/// Adds `claim` to the claims owned by `who`.
+		fn add_owned_claim(who: &T::AccountId, claim: H256) -> DispatchResult {
+			ClaimsOf::<T>::try_mutate(who, |claims| claims.try_push(claim))
+				.map_err(|_| Error::<T>::TooManyClaims.into())
+		}
+
+		/// Removes `claim` from the claims owned by `who`, keeping the order of the others.
+		fn remove_owned_claim(who: &T::AccountId, claim: &H256) {
+			ClaimsOf::<T>::mutate_exists(who, |maybe_claims| {
+				if let Some(claims) = maybe_claims {
+					if let Some(index) = claims.iter().position(|owned| owned == claim) {
+						claims.remove(index);
+					}
+					if claims.is_empty() {
+						*maybe_claims = None;
+					}
+				}
+			});
+		}
+
+		/// Up to `limit` claims owned by `owner`, from the `start`th one, oldest first.
+		pub fn claims_of(owner: T::AccountId, start: u32, limit: u32) -> Vec<H256> {
+			Self::owned_claims(owner)
+				.iter()
+				.skip(start as usize)
+				.take(limit as usize)
+				.copied()
+				.collect()
+		}
+
+		/// Removes a claim with everything attached to it, refunding its deposits.
+		fn remove_claim(claim: &H256, proof: &ProofOf<T>) {
+			Self::remove_owned_claim(&proof.owner, claim);
+			Proofs::<T>::remove(claim);
+			ClaimInfos::<T>::remove(claim);
+			Self::remove_claim_expiry(claim);
+			Batches::<T>::remove(claim);
+			Self::remove_offer(claim);
+			Self::remove_endorsements(claim);
+			Self::record_custody(claim, None);
+			T::Currency::unreserve(&proof.owner, proof.deposit);
+		}
+
+		/// Drops the offer of a claim, freeing its slot among the offers expiring with it.
+		fn remove_offer(claim: &H256) {
+			if let Some(offer) = Offers::<T>::take(claim) {
+				OffersExpiring::<T>::mutate_exists(offer.expiry, |maybe_claims| {
+					if let Some(claims) = maybe_claims {
+						if let Some(index) = claims.iter().position(|expiring| expiring == claim) {
+							claims.swap_remove(index);
+						}
+						if claims.is_empty() {
+							*maybe_claims = None;
+						}
+					}
+				});
+			}
+		}
+
+		/// Schedules `claim` to expire at `expiry`, instead of any expiry it had.
+		fn set_claim_expiry(claim: &H256, expiry: T::BlockNumber) -> DispatchResult {
+			ClaimsExpiring::<T>::try_mutate(&expiry, |claims| claims.try_push(*claim))
+				.map_err(|_| Error::<T>::TooManyClaimsExpiring)?;
+			Self::remove_claim_expiry(claim);
+			ClaimExpiry::<T>::insert(claim, expiry);
+			if Self::next_claim_expiry_block().is_none() {
+				NextClaimExpiryBlock::<T>::put(frame_system::Pallet::<T>::block_number());
+			}
+			Ok(())
+		}
+
+		/// Drops the expiry of a claim, freeing its slot among the claims expiring with it.
+		fn remove_claim_expiry(claim: &H256) {
+			if let Some(expiry) = ClaimExpiry::<T>::take(claim) {
+				ClaimsExpiring::<T>::mutate_exists(expiry, |maybe_claims| {
+					if let Some(claims) = maybe_claims {
+						if let Some(index) = claims.iter().position(|expiring| expiring == claim) {
+							claims.swap_remove(index);
+						}
+						if claims.is_empty() {
+							*maybe_claims = None;
+						}
+					}
+				});
+			}
+		}
+
+		/// Moves the claims of the first version of `Proofs`, keyed by their raw bytes and
+		/// holding only the owner and block, to digest keys.
+		///
+		/// A 32 byte key is taken as a digest already, other keys are hashed with
+		/// `ClaimHashing` as a preimage. The migrated claims hold no deposit and get empty
+		/// metadata.
+		fn migrate_proofs() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() >= STORAGE_VERSION {
+				return T::DbWeight::get().reads(1)
+			}
+
+			let module = <Pallet<T> as PalletInfoAccess>::name();
+			let old: Vec<(Vec<u8>, (T::AccountId, T::BlockNumber))> =
+				storage_key_iter::<Vec<u8>, (T::AccountId, T::BlockNumber), Blake2_128Concat>(
+					module.as_bytes(),
+					b"Proofs",
+				)
+				.drain()
+				.collect();
+
+			let count = old.len() as Weight;
+			for (key, (owner, block_number)) in old {
+				let claim = if key.len() == 32 {
+					H256::from_slice(&key)
+				} else {
+					T::ClaimHashing::get().hash(&key)
+				};
+				if Self::add_owned_claim(&owner, claim).is_err() {
+					log::warn!("📜 {:?} owns too many claims to index.", owner);
+				}
+				let proof = Proof {
+					owner: owner.clone(),
+					block_number,
+					algorithm: T::ClaimHashing::get(),
+					deposit: Zero::zero(),
+				};
+				let info = ClaimInfo::<T> {
+					title: Default::default(),
+					content_type: Default::default(),
+					content_length: 0,
+					uri: None,
+					created_at: Default::default(),
+					version: 1,
+				};
+				let custody = Custody {
+					owner,
+					from_block: block_number,
+					to_block: None,
+					reason: CustodyReason::Created,
+				};
+				let mut history = BoundedVec::<_, T::MaxHistoryLength>::default();
+				let _ = history.try_push(custody);
+				Proofs::<T>::insert(&claim, proof);
+				ClaimInfos::<T>::insert(&claim, info);
+				History::<T>::insert(&claim, history);
+			}
+			STORAGE_VERSION.put::<Pallet<T>>();
+
+			log::info!("📜 Migrated {} claims to digest keys.", count);
+			T::DbWeight::get().reads_writes(1 + 2 * count, 1 + 5 * count)
+		}
+
+		/// Removes expired claims block by block, as far as `remaining_weight` allows.
+		///
+		/// A block whose claims do not all fit is left with the rest for a later block.
+		fn sweep_expired_claims(n: T::BlockNumber, remaining_weight: Weight) -> Weight {
+			let db = T::DbWeight::get();
+			let per_block = db.reads_writes(1, 2);
+			let per_claim = db.reads_writes(5, 10).saturating_add(
+				db.reads_writes(1, 1).saturating_mul(T::MaxEndorsements::get().into()),
+			);
+			let mut used = db.reads_writes(1, 1);
+			if used > remaining_weight {
+				return 0
+			}
+
+			let mut block = match Self::next_claim_expiry_block() {
+				Some(block) => block,
+				None => return db.reads(1),
+			};
+			while block <= n && used.saturating_add(per_block) <= remaining_weight {
+				used = used.saturating_add(per_block);
+
+				let mut expiring = ClaimsExpiring::<T>::take(&block).into_inner();
+				while used.saturating_add(per_claim) <= remaining_weight {
+					match expiring.pop() {
+						Some(claim) => Self::expire_claim(&claim, block),
+						None => break,
+					}
+					used = used.saturating_add(per_claim);
+				}
+
+				if !expiring.is_empty() {
+					// the rest is swept in a later block
+					let rest: BoundedVec<_, T::MaxClaimsExpiringPerBlock> =
+						expiring.try_into().unwrap_or_default();
+					ClaimsExpiring::<T>::insert(&block, rest);
+					break
+				}
+				block = block.saturating_add(One::one());
+			}
+
+			NextClaimExpiryBlock::<T>::put(block);
+			used
+		}
+
+		/// Removes a claim which expired at `block`.
+		fn expire_claim(claim: &H256, block: T::BlockNumber) {
+			// skip claims which were renewed or revoked since
+			if Self::claim_expiry(claim) != Some(block) {
+				return
+			}
+			if let Some(proof) = Proofs::<T>::get(claim) {
+				Self::remove_claim(claim, &proof);
+				Self::deposit_event(Event::ClaimExpired(proof.owner, *claim, proof.deposit));
+			}
+		}
+
+		/// The endorsements of a claim, `verified` only while the endorser is still an attestor.
+		pub fn endorsements(claim: &H256) -> Vec<EndorsementOf<T>> {
+			let mut endorsements = Endorsements::<T>::get(claim).into_inner();
+			for endorsement in endorsements.iter_mut() {
+				endorsement.verified &= Attestors::<T>::contains_key(&endorsement.endorser);
+			}
+			endorsements
+		}
+
+		/// Drops the endorsement of `endorser` from a claim, refunding its deposit.
+		fn take_endorsement(claim: &H256, endorser: &T::AccountId) -> DispatchResult {
+			let mut endorsements = Endorsements::<T>::get(claim);
+			let index = endorsements
+				.iter()
+				.position(|endorsement| endorsement.endorser == *endorser)
+				.ok_or(Error::<T>::EndorsementNotExist)?;
+			let endorsement = endorsements.remove(index);
+			T::Currency::unreserve(endorser, endorsement.deposit);
+			Endorsements::<T>::insert(claim, endorsements);
+			Ok(())
+		}
+
+		/// Drops the endorsements of a claim, refunding their deposits.
+		fn remove_endorsements(claim: &H256) {
+			for endorsement in Endorsements::<T>::take(claim).into_inner() {
+				T::Currency::unreserve(&endorsement.endorser, endorsement.deposit);
+			}
+		}
+
+		/// Frees the slot of a pending claim among the pending claims expiring at `expiry`.
+		fn remove_pending_expiring(claim: &H256, expiry: T::BlockNumber) {
+			PendingExpiring::<T>::mutate_exists(expiry, |maybe_claims| {
+				if let Some(claims) = maybe_claims {
+					if let Some(index) = claims.iter().position(|expiring| expiring == claim) {
+						claims.swap_remove(index);
+					}
+					if claims.is_empty() {
+						*maybe_claims = None;
+					}
+				}
+			});
+		}
+
+		/// Drops a pending claim expiring at `n`, refunding its deposit.
+		fn expire_pending_claim(claim: &H256, n: T::BlockNumber) {
+			if let Some(pending) = PendingClaims::<T>::get(claim) {
+				// the claim may have been finalized and proposed again since
+				if pending.expiry == n {
+					PendingClaims::<T>::remove(claim);
+					T::Currency::unreserve(&pending.proposer, pending.deposit);
+					Self::deposit_event(Event::PendingClaimExpired(*claim, pending.deposit));
+				}
+			}
+		}
+
+		/// Moves a claim and its deposit to `dest`.
+		fn do_trans_claim(claim: H256, dest: T::AccountId) -> DispatchResult {
+			//check proofs if it's exist.
+			let mut proof = Proofs::<T>::get(&claim).ok_or(Error::<T>::ClaimNotExist)?;
+			let who = proof.owner.clone();
+
+			//move the claim to the index of the new owner
+			Self::add_owned_claim(&dest, claim)?;
+			Self::remove_owned_claim(&who, &claim);
+
+			//move the deposit to the new owner
+			T::Currency::repatriate_reserved(&who, &dest, proof.deposit, BalanceStatus::Reserved)?;
+
+			//insert the claim
+			let deposit = proof.deposit;
+			proof.owner = dest.clone();
+			proof.block_number = frame_system::Pallet::<T>::block_number();
+			Proofs::<T>::insert(&claim,proof);
+			Self::record_custody(&claim, Some((dest.clone(), CustodyReason::Transferred)));
+
+			Self::deposit_event(Event::ClaimTrans(who,dest, claim,deposit));
+
+			Ok(())
+		}
+
+		/// Ends the current custody of `claim`, and starts the custody of the next owner if any.
+		fn record_custody(claim: &H256, next: Option<(T::AccountId, CustodyReason)>) {
+			let now = frame_system::Pallet::<T>::block_number();
+			History::<T>::mutate(claim, |history| {
+				let mut entries = sp_std::mem::take(history).into_inner();
+				if let Some(last) = entries.last_mut() {
+					if last.to_block.is_none() {
+						last.to_block = Some(now);
+					}
+				}
+				if let Some((owner, reason)) = next {
+					if !entries.is_empty() && entries.len() >= T::MaxHistoryLength::get() as usize {
+						entries.remove(0);
+					}
+					entries.push(Custody { owner, from_block: now, to_block: None, reason });
+				}
+				*history = entries.try_into().unwrap_or_default();
+			});
+		}
+
+		/// The full chain of custody of a claim, oldest first.
+		pub fn provenance(claim: H256) -> Vec<CustodyOf<T>> {
+			Self::history(claim).into_inner()
+		}
+
+		/// Whether `leaf` is the leaf at `index` of the batch anchored at `root`.
+		pub fn verify_inclusion(root: H256, leaf: H256, index: u32, proof: Vec<H256>) -> bool {
+			Self::batch_leaf_count(root).map_or(false, |leaf_count| {
+				crate::merkle::verify_proof(&root, &leaf, index, leaf_count, &proof)
+			})
+		}
+
+		/// The owner of a claim at block `at`.
+		///
+		/// A claim changing hands in a block counts as held by its new owner in that block.
+		pub fn holder_at(claim: H256, at: T::BlockNumber) -> Option<T::AccountId> {
+			Self::history(claim)
+				.iter()
+				.rev()
+				.find(|custody| {
+					custody.from_block <= at && custody.to_block.map_or(true, |to| at < to)
+				})
+				.map(|custody| custody.owner.clone())
+		}
+
+		/// Bounds the submitted metadata into a first version of `ClaimInfo`.
+		fn claim_info_from(
+			metadata: ClaimMetadata,
+			created_at: MomentOf<T>,
+		) -> Result<ClaimInfo<T>, DispatchError> {
+			let uri = match metadata.uri {
+				Some(uri) => Some(uri.try_into().map_err(|_| Error::<T>::UriTooLong)?),
+				None => None,
+			};
+			Ok(ClaimInfo {
+				title: metadata.title.try_into().map_err(|_| Error::<T>::TitleTooLong)?,
+				content_type: metadata
+					.content_type
+					.try_into()
+					.map_err(|_| Error::<T>::ContentTypeTooLong)?,
+				content_length: metadata.content_length,
+				uri,
+				created_at,
+				version: 1,
+			})
+		}
+
+		/// The deposit of a claim: the base plus a fee for every byte of its keys and values.
+		pub fn claim_deposit(proof: &ProofOf<T>, info: &ClaimInfo<T>) -> BalanceOf<T> {
+			let bytes = (2 * H256::len_bytes() + proof.encoded_size() + info.encoded_size()) as u32;
+			T::ClaimDepositBase::get()
+				.saturating_add(T::ClaimDepositPerByte::get().saturating_mul(bytes.into()))
+		}
 	}
 }
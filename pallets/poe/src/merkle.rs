@@ -0,0 +1,111 @@
+//! Binary Merkle trees of document digests, anchored as claims with `anchor_batch`.
+//!
+//! Leaves and nodes are hashed with blake2_256 behind different prefixes, so a node can never
+//! pass for a leaf. A node without a sibling is carried up to the next level unchanged.
+
+use sp_core::H256;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// The hash of a leaf of the tree, from a document digest.
+pub fn hash_leaf(leaf: &H256) -> H256 {
+	let mut data = [0u8; 33];
+	data[0] = LEAF_PREFIX;
+	data[1..].copy_from_slice(leaf.as_bytes());
+	H256(sp_io::hashing::blake2_256(&data))
+}
+
+/// The hash of an inner node of the tree, from its two children.
+pub fn hash_node(left: &H256, right: &H256) -> H256 {
+	let mut data = [0u8; 65];
+	data[0] = NODE_PREFIX;
+	data[1..33].copy_from_slice(left.as_bytes());
+	data[33..].copy_from_slice(right.as_bytes());
+	H256(sp_io::hashing::blake2_256(&data))
+}
+
+/// Checks `leaf` is the leaf at `index` of the tree of `leaf_count` leaves with the given `root`.
+///
+/// `proof` holds the siblings on the path from the leaf to the root, bottom up, as built by
+/// `MerkleTree::proof`.
+pub fn verify_proof(root: &H256, leaf: &H256, index: u32, leaf_count: u32, proof: &[H256]) -> bool {
+	if index >= leaf_count {
+		return false
+	}
+
+	let mut siblings = proof.iter();
+	let mut hash = hash_leaf(leaf);
+	let (mut index, mut width) = (index, leaf_count);
+	while width > 1 {
+		if index % 2 == 1 {
+			match siblings.next() {
+				Some(sibling) => hash = hash_node(sibling, &hash),
+				None => return false,
+			}
+		} else if index + 1 < width {
+			match siblings.next() {
+				Some(sibling) => hash = hash_node(&hash, sibling),
+				None => return false,
+			}
+		}
+		index /= 2;
+		width = (width + 1) / 2;
+	}
+
+	siblings.next().is_none() && hash == *root
+}
+
+/// A tree built off-chain from the digests of a batch of documents.
+#[cfg(feature = "std")]
+pub struct MerkleTree {
+	/// The levels of the tree, from the hashed leaves up to the root.
+	levels: Vec<Vec<H256>>,
+}
+
+#[cfg(feature = "std")]
+impl MerkleTree {
+	/// Builds the tree of `leaves`, `None` if there are none or more than `u32::MAX`.
+	pub fn new(leaves: &[H256]) -> Option<Self> {
+		if leaves.is_empty() || leaves.len() > u32::MAX as usize {
+			return None
+		}
+
+		let mut levels = vec![leaves.iter().map(hash_leaf).collect::<Vec<_>>()];
+		while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+			let next = level
+				.chunks(2)
+				.map(|pair| pair.get(1).map_or(pair[0], |right| hash_node(&pair[0], right)))
+				.collect();
+			levels.push(next);
+		}
+		Some(Self { levels })
+	}
+
+	/// The root to anchor with `anchor_batch`.
+	pub fn root(&self) -> H256 {
+		*self.levels.last().and_then(|level| level.first()).expect("a tree has a leaf; qed")
+	}
+
+	/// The number of leaves to anchor with `anchor_batch`.
+	pub fn leaf_count(&self) -> u32 {
+		self.levels[0].len() as u32
+	}
+
+	/// The proof of the leaf at `index`, `None` if there is no such leaf.
+	pub fn proof(&self, index: u32) -> Option<Vec<H256>> {
+		let mut index = index as usize;
+		if index >= self.levels[0].len() {
+			return None
+		}
+
+		let mut proof = Vec::new();
+		for level in &self.levels[..self.levels.len() - 1] {
+			if let Some(sibling) = level.get(index ^ 1) {
+				proof.push(*sibling);
+			}
+			index /= 2;
+		}
+		Some(proof)
+	}
+}
@@ -1,5 +1,9 @@
 use crate as pallet_poe;
-use frame_support::parameter_types;
+use frame_support::{
+	parameter_types,
+	traits::{GenesisBuild, Hooks},
+	weights::{RuntimeDbWeight, Weight},
+};
 use frame_system as system;
 use sp_core::H256;
 use sp_runtime::{
@@ -24,6 +28,8 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
+		Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
 		PoeModule: pallet_poe::{Pallet, Call, Storage, Event<T>},
 	}
 );
@@ -31,13 +37,15 @@ frame_support::construct_runtime!(
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
 	pub const SS58Prefix: u8 = 42;
+	// Unit weights, so the tests can run out of idle weight.
+	pub const TestDbWeight: RuntimeDbWeight = RuntimeDbWeight { read: 1, write: 1 };
 }
 
 impl system::Config for Test {
 	type BaseCallFilter = frame_support::traits::Everything;
 	type BlockWeights = ();
 	type BlockLength = ();
-	type DbWeight = ();
+	type DbWeight = TestDbWeight;
 	type Origin = Origin;
 	type Call = Call;
 	type Index = u64;
@@ -51,7 +59,7 @@ impl system::Config for Test {
 	type BlockHashCount = BlockHashCount;
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -59,16 +67,101 @@ impl system::Config for Test {
 	type OnSetCode = ();
 }
 
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+
+impl pallet_balances::Config for Test {
+	type AccountStore = System;
+	type Balance = u64;
+	type DustRemoval = ();
+	type Event = Event;
+	type ExistentialDeposit = ExistentialDeposit;
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const MinimumPeriod: u64 = 5;
+}
+
+impl pallet_timestamp::Config for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+	type WeightInfo = ();
+}
+
+pub const NOW: u64 = 42_000;
+
 parameter_types! {
 	pub const MaxLength: u32 = 6;
+	pub const ClaimHashing: pallet_poe::HashAlgorithm = pallet_poe::HashAlgorithm::Blake2_256;
+	pub const ClaimDepositBase: u64 = 100;
+	pub const ClaimDepositPerByte: u64 = 1;
+	pub const MaxTitleLength: u32 = 8;
+	pub const MaxContentTypeLength: u32 = 16;
+	pub const MaxUriLength: u32 = 16;
+	pub const MaxHistoryLength: u32 = 3;
+	pub const OfferPeriod: u64 = 5;
+	pub const MaxOffersExpiringPerBlock: u32 = 2;
+	pub const MaxSigners: u32 = 3;
+	pub const PendingClaimPeriod: u64 = 5;
+	pub const MaxPendingExpiringPerBlock: u32 = 2;
+	pub const MaxEndorsements: u32 = 1;
+	pub const EndorsementDeposit: u64 = 50;
+	pub const MaxClaimsExpiringPerBlock: u32 = 2;
+	pub const RenewalFeePerBlock: u64 = 2;
+	pub const MaxClaimsPerAccount: u32 = 3;
 }
 
 impl pallet_poe::Config for Test {
 	type Event = Event;
 	type Length = MaxLength;
+	type ClaimHashing = ClaimHashing;
+	type Currency = Balances;
+	type ClaimDepositBase = ClaimDepositBase;
+	type ClaimDepositPerByte = ClaimDepositPerByte;
+	type Time = Timestamp;
+	type MaxTitleLength = MaxTitleLength;
+	type MaxContentTypeLength = MaxContentTypeLength;
+	type MaxUriLength = MaxUriLength;
+	type MaxHistoryLength = MaxHistoryLength;
+	type OfferPeriod = OfferPeriod;
+	type MaxOffersExpiringPerBlock = MaxOffersExpiringPerBlock;
+	type MaxSigners = MaxSigners;
+	type PendingClaimPeriod = PendingClaimPeriod;
+	type MaxPendingExpiringPerBlock = MaxPendingExpiringPerBlock;
+	type MaxEndorsements = MaxEndorsements;
+	type EndorsementDeposit = EndorsementDeposit;
+	type AttestorOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxClaimsExpiringPerBlock = MaxClaimsExpiringPerBlock;
+	type RenewalFeePerBlock = RenewalFeePerBlock;
+	type OnRenewalFee = ();
+	type MaxClaimsPerAccount = MaxClaimsPerAccount;
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: vec![(ALICE, 10000), (BOB, 10000)] }
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(NOW);
+	});
+	ext
+}
+
+pub fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		PoeModule::on_initialize(System::block_number());
+		PoeModule::on_idle(System::block_number(), Weight::max_value());
+	}
 }
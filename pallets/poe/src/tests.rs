@@ -1,15 +1,38 @@
 use super::*;
 use crate::mock::*;
-use frame_support::{assert_noop, assert_ok};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	storage::migration::put_storage_value,
+	traits::{Hooks, StorageVersion},
+	Blake2_128Concat, StorageHasher,
+};
+use sp_core::H256;
+use sp_runtime::DispatchError;
+
+fn metadata() -> ClaimMetadata {
+	ClaimMetadata {
+		title: b"doc".to_vec(),
+		content_type: b"text/plain".to_vec(),
+		content_length: 4,
+		uri: None,
+	}
+}
+
 #[test]
 fn create_claim_should_works() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
 		assert_eq!(
 			Proofs::<Test>::get(&claim),
-			Some((ALICE, frame_system::Pallet::<Test>::block_number())),
+			Some(Proof {
+				owner: ALICE,
+				block_number: frame_system::Pallet::<Test>::block_number(),
+				algorithm: HashAlgorithm::Blake2_256,
+				deposit: DEPOSIT,
+			}),
 		);
 	});
 }
@@ -18,27 +41,38 @@ fn create_claim_should_works() {
 fn create_claim_error_by_already_exist() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
+		let claim = H256::repeat_byte(1);
 
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
 
 		assert_noop!(
-			PoeModule::create_claim(Origin::signed(ALICE), claim.clone()),
+			PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None),
 			Error::<Test>::ProofAlreadyExist
 		);
 	});
 }
 
+fn transfer(from: AccountId, claim: H256, to: AccountId) {
+	assert_ok!(PoeModule::offer_claim(Origin::signed(from), claim, to.clone()));
+	assert_ok!(PoeModule::accept_claim(Origin::signed(to), claim));
+}
+
 #[test]
-fn trans_claim_should_works() {
+fn accept_claim_should_works() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
-		assert_ok!(PoeModule::trans_claim(Origin::signed(ALICE), claim.clone(),BOB));
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim,BOB));
+		assert_ok!(PoeModule::accept_claim(Origin::signed(BOB), claim));
 		assert_eq!(
 			Proofs::<Test>::get(&claim),
-			Some((BOB, frame_system::Pallet::<Test>::block_number())),
+			Some(Proof {
+				owner: BOB,
+				block_number: frame_system::Pallet::<Test>::block_number(),
+				algorithm: HashAlgorithm::Blake2_256,
+				deposit: DEPOSIT,
+			}),
 		);
 	});
 }
@@ -46,13 +80,13 @@ fn trans_claim_should_works() {
 
 
 #[test]
-fn trans_claim_error_by_not_exist() {
+fn offer_claim_error_by_not_exist() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
-		let others = vec![0, 1];
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
-		assert_noop!(PoeModule::trans_claim(Origin::signed(ALICE), others.clone(),BOB),Error::<Test>::ClaimNotExist);
+		let claim = H256::repeat_byte(1);
+		let others = H256::repeat_byte(2);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(PoeModule::offer_claim(Origin::signed(ALICE), others,BOB),Error::<Test>::ClaimNotExist);
 
 	});
 }
@@ -60,12 +94,12 @@ fn trans_claim_error_by_not_exist() {
 
 
 #[test]
-fn trans_claim_error_by_not_claim_owner() {
+fn offer_claim_error_by_not_claim_owner() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
-		assert_noop!(PoeModule::trans_claim(Origin::signed(BOB), claim.clone(),ALICE),Error::<Test>::NotClaimOwner);
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(PoeModule::offer_claim(Origin::signed(BOB), claim,ALICE),Error::<Test>::NotClaimOwner);
 
 	});
 }
@@ -75,9 +109,9 @@ fn trans_claim_error_by_not_claim_owner() {
 fn revoked_claim_should_works() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
-		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claim.clone()));
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claim));
 		assert_eq!(
 			Proofs::<Test>::get(&claim),
 			None,
@@ -91,10 +125,10 @@ fn revoked_claim_should_works() {
 fn revoked_claim_error_by_not_exist() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
-		let others = vec![0, 1];
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
-		assert_noop!(PoeModule::revoked_claim(Origin::signed(ALICE), others.clone()),Error::<Test>::ClaimNotExist);
+		let claim = H256::repeat_byte(1);
+		let others = H256::repeat_byte(2);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(PoeModule::revoked_claim(Origin::signed(ALICE), others),Error::<Test>::ClaimNotExist);
 
 	});
 }
@@ -106,9 +140,9 @@ fn revoked_claim_error_by_not_exist() {
 fn revoked_claim_error_by_not_claim_owner() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3];
-		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()));
-		assert_noop!(PoeModule::revoked_claim(Origin::signed(BOB), claim.clone()),Error::<Test>::NotClaimOwner);
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(PoeModule::revoked_claim(Origin::signed(BOB), claim),Error::<Test>::NotClaimOwner);
 
 	});
 }
@@ -119,8 +153,1129 @@ fn revoked_claim_error_by_not_claim_owner() {
 fn create_claim_error_by_claim_over_length() {
 	new_test_ext().execute_with(|| {
 		// Dispatch a signed extrinsic.
-		let claim = vec![0, 1, 2, 3,4,5,6];
-		assert_noop!(PoeModule::create_claim(Origin::signed(ALICE), claim.clone()),Error::<Test>::ClaimOverLength);
+		let preimage = vec![0, 1, 2, 3,4,5,6];
+		assert_noop!(PoeModule::create_claim_from_preimage(Origin::signed(ALICE), preimage, metadata(), None),Error::<Test>::ClaimOverLength);
+
+	});
+}
+
+#[test]
+fn create_claim_from_preimage_should_works() {
+	new_test_ext().execute_with(|| {
+		let preimage = vec![0, 1, 2, 3];
+		assert_ok!(PoeModule::create_claim_from_preimage(
+			Origin::signed(ALICE),
+			preimage.clone(),
+			metadata(),
+			None,
+		));
+
+		let claim = H256(sp_io::hashing::blake2_256(&preimage));
+		assert_eq!(PoeModule::proofs(&claim).map(|proof| proof.owner), Some(ALICE));
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(BOB), claim, metadata(), None),
+			Error::<Test>::ProofAlreadyExist
+		);
+	});
+}
+
+#[test]
+fn hash_algorithm_should_works() {
+	let data = b"abc";
+	assert_eq!(HashAlgorithm::Blake2_256.hash(data), H256(sp_io::hashing::blake2_256(data)));
+	assert_eq!(HashAlgorithm::Sha2_256.hash(data), H256(sp_io::hashing::sha2_256(data)));
+	assert_eq!(HashAlgorithm::Keccak256.hash(data), H256(sp_io::hashing::keccak_256(data)));
+	assert_ne!(HashAlgorithm::Sha2_256.hash(data), HashAlgorithm::Keccak256.hash(data));
+}
+
+// 100 base plus 149 bytes: the two 32 byte keys, the owner, the block number, the algorithm, the
+// deposit itself and the 36 bytes of `metadata()`.
+const DEPOSIT: u64 = 249;
+
+#[test]
+fn create_claim_should_reserve_deposit() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+
+		assert_eq!(Balances::reserved_balance(ALICE), DEPOSIT);
+		assert_eq!(PoeModule::proofs(&claim).map(|proof| proof.deposit), Some(DEPOSIT));
+		System::assert_last_event(Event::PoeModule(crate::Event::ClaimCreate(
+			ALICE,
+			claim,
+			HashAlgorithm::Blake2_256,
+			DEPOSIT,
+		)));
+	});
+}
+
+#[test]
+fn create_claim_error_by_insufficient_balance() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(COCO), H256::repeat_byte(1), metadata(), None),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn accept_claim_should_move_deposit() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, COCO));
+		assert_eq!(Balances::reserved_balance(ALICE), DEPOSIT);
+		assert_eq!(Balances::reserved_balance(COCO), 0);
+
+		assert_ok!(PoeModule::accept_claim(Origin::signed(COCO), claim));
+
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 10000 - DEPOSIT);
+		assert_eq!(Balances::reserved_balance(COCO), DEPOSIT);
+		System::assert_last_event(Event::PoeModule(crate::Event::ClaimTrans(
+			ALICE,
+			COCO,
+			claim,
+			DEPOSIT,
+		)));
+	});
+}
+
+#[test]
+fn revoked_claim_should_unreserve_deposit() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claim));
+
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::free_balance(ALICE), 10000);
+		System::assert_last_event(Event::PoeModule(crate::Event::ClaimRevoked(
+			ALICE,
+			claim,
+			DEPOSIT,
+		)));
+	});
+}
+
+#[test]
+fn create_claim_should_store_claim_info() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+
+		let info = PoeModule::claim_info(&claim).unwrap();
+		assert_eq!(info.title.into_inner(), b"doc".to_vec());
+		assert_eq!(info.content_type.into_inner(), b"text/plain".to_vec());
+		assert_eq!(info.content_length, 4);
+		assert_eq!(info.uri, None);
+		assert_eq!(info.created_at, NOW);
+		assert_eq!(info.version, 1);
+	});
+}
+
+#[test]
+fn create_claim_error_by_metadata_too_long() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		let title = ClaimMetadata { title: b"too long!".to_vec(), ..metadata() };
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(ALICE), claim, title, None),
+			Error::<Test>::TitleTooLong
+		);
+		let content_type = ClaimMetadata { content_type: vec![b'a'; 17], ..metadata() };
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(ALICE), claim, content_type, None),
+			Error::<Test>::ContentTypeTooLong
+		);
+		let uri = ClaimMetadata { uri: Some(vec![b'a'; 17]), ..metadata() };
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(ALICE), claim, uri, None),
+			Error::<Test>::UriTooLong
+		);
+	});
+}
+
+#[test]
+fn update_claim_info_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		Timestamp::set_timestamp(NOW + 6_000);
+
+		// 9 more bytes for the uri: its length and 8 bytes.
+		let update = ClaimMetadata { uri: Some(b"ipfs://Q".to_vec()), ..metadata() };
+		assert_ok!(PoeModule::update_claim_info(Origin::signed(ALICE), claim, update));
+
+		let info = PoeModule::claim_info(&claim).unwrap();
+		assert_eq!(info.uri.map(|uri| uri.into_inner()), Some(b"ipfs://Q".to_vec()));
+		assert_eq!(info.created_at, NOW);
+		assert_eq!(info.version, 2);
+		assert_eq!(Balances::reserved_balance(ALICE), DEPOSIT + 9);
+		System::assert_last_event(Event::PoeModule(crate::Event::ClaimInfoUpdated(
+			ALICE,
+			claim,
+			2,
+			DEPOSIT + 9,
+		)));
+
+		// shrinking the metadata refunds part of the deposit
+		assert_ok!(PoeModule::update_claim_info(Origin::signed(ALICE), claim, metadata()));
+		assert_eq!(PoeModule::claim_info(&claim).map(|info| info.version), Some(3));
+		assert_eq!(Balances::reserved_balance(ALICE), DEPOSIT);
+	});
+}
+
+#[test]
+fn update_claim_info_error_by_not_claim_owner() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(
+			PoeModule::update_claim_info(Origin::signed(BOB), claim, metadata()),
+			Error::<Test>::NotClaimOwner
+		);
+		assert_noop!(
+			PoeModule::update_claim_info(Origin::signed(ALICE), H256::repeat_byte(2), metadata()),
+			Error::<Test>::ClaimNotExist
+		);
+	});
+}
+
+#[test]
+fn revoked_claim_should_remove_claim_info() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claim));
+		assert_eq!(PoeModule::claim_info(&claim), None);
+	});
+}
+
+fn custody(owner: AccountId, from: u64, to: Option<u64>, reason: CustodyReason) -> CustodyOf<Test> {
+	Custody { owner, from_block: from, to_block: to, reason }
+}
+
+#[test]
+fn provenance_should_record_chain_of_custody() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		System::set_block_number(5);
+		transfer(ALICE, claim, BOB);
+		System::set_block_number(9);
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(BOB), claim));
+
+		assert_eq!(
+			PoeModule::provenance(claim),
+			vec![
+				custody(ALICE, 1, Some(5), CustodyReason::Created),
+				custody(BOB, 5, Some(9), CustodyReason::Transferred),
+			]
+		);
+		assert_eq!(PoeModule::holder_at(claim, 0), None);
+		assert_eq!(PoeModule::holder_at(claim, 4), Some(ALICE));
+		assert_eq!(PoeModule::holder_at(claim, 5), Some(BOB));
+		assert_eq!(PoeModule::holder_at(claim, 9), None);
+	});
+}
+
+#[test]
+fn provenance_should_drop_oldest_custody() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		let transfers = vec![(ALICE, BOB), (BOB, ALICE), (ALICE, BOB)];
+		for (block, (from, to)) in transfers.into_iter().enumerate() {
+			System::set_block_number(block as u64 + 2);
+			transfer(from, claim, to);
+		}
+
+		assert_eq!(
+			PoeModule::provenance(claim),
+			vec![
+				custody(BOB, 2, Some(3), CustodyReason::Transferred),
+				custody(ALICE, 3, Some(4), CustodyReason::Transferred),
+				custody(BOB, 4, None, CustodyReason::Transferred),
+			]
+		);
+		assert_eq!(PoeModule::holder_at(claim, 1), None);
+		assert_eq!(PoeModule::holder_at(claim, 10), Some(BOB));
+	});
+}
+
+#[test]
+fn offer_claim_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+
+		assert_eq!(PoeModule::offers(&claim), Some(Offer { dest: BOB, expiry: 6 }));
+		assert_eq!(PoeModule::proofs(&claim).map(|proof| proof.owner), Some(ALICE));
+		System::assert_last_event(Event::PoeModule(crate::Event::ClaimOffered(
+			ALICE,
+			BOB,
+			claim,
+			6,
+		)));
+	});
+}
+
+#[test]
+fn offer_claim_error_by_offer_already_exist() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(
+			PoeModule::offer_claim(Origin::signed(ALICE), claim, ALICE),
+			Error::<Test>::CannotOfferToSelf
+		);
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+		assert_noop!(
+			PoeModule::offer_claim(Origin::signed(ALICE), claim, COCO),
+			Error::<Test>::OfferAlreadyExist
+		);
+	});
+}
+
+#[test]
+fn offer_claim_error_by_too_many_offers_expiring() {
+	new_test_ext().execute_with(|| {
+		for byte in 1..=3 {
+			let claim = H256::repeat_byte(byte);
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+			if byte < 3 {
+				assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+			} else {
+				assert_noop!(
+					PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB),
+					Error::<Test>::TooManyOffersExpiring
+				);
+			}
+		}
+	});
+}
+
+#[test]
+fn answered_offers_should_free_expiring_slots() {
+	new_test_ext().execute_with(|| {
+		let claims: Vec<H256> = (1..=4).map(H256::repeat_byte).collect();
+		for claim in claims.iter() {
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), *claim, metadata(), None));
+		}
+
+		// cancelled, rejected, accepted and revoked offers all give their slot back
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claims[0], BOB));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claims[1], BOB));
+		assert_ok!(PoeModule::cancel_offer(Origin::signed(ALICE), claims[0]));
+		assert_ok!(PoeModule::reject_claim(Origin::signed(BOB), claims[1]));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claims[2], BOB));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claims[3], BOB));
+		assert_ok!(PoeModule::accept_claim(Origin::signed(BOB), claims[2]));
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claims[3]));
+
+		let expiry = System::block_number() + OfferPeriod::get();
+		assert!(!OffersExpiring::<Test>::contains_key(expiry));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claims[0], BOB));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claims[1], BOB));
+	});
+}
+
+#[test]
+fn accept_claim_error_by_not_offer_recipient() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(
+			PoeModule::accept_claim(Origin::signed(BOB), claim),
+			Error::<Test>::OfferNotExist
+		);
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+		assert_noop!(
+			PoeModule::accept_claim(Origin::signed(COCO), claim),
+			Error::<Test>::NotOfferRecipient
+		);
+	});
+}
+
+#[test]
+fn reject_claim_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+		assert_noop!(
+			PoeModule::reject_claim(Origin::signed(COCO), claim),
+			Error::<Test>::NotOfferRecipient
+		);
+		assert_ok!(PoeModule::reject_claim(Origin::signed(BOB), claim));
+
+		assert_eq!(PoeModule::offers(&claim), None);
+		assert_eq!(PoeModule::proofs(&claim).map(|proof| proof.owner), Some(ALICE));
+		assert_eq!(Balances::reserved_balance(ALICE), DEPOSIT);
+		System::assert_last_event(Event::PoeModule(crate::Event::OfferRejected(BOB, claim)));
+	});
+}
+
+#[test]
+fn cancel_offer_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(
+			PoeModule::cancel_offer(Origin::signed(ALICE), claim),
+			Error::<Test>::OfferNotExist
+		);
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+		assert_noop!(
+			PoeModule::cancel_offer(Origin::signed(BOB), claim),
+			Error::<Test>::NotClaimOwner
+		);
+		assert_ok!(PoeModule::cancel_offer(Origin::signed(ALICE), claim));
+
+		assert_eq!(PoeModule::offers(&claim), None);
+		assert_noop!(
+			PoeModule::accept_claim(Origin::signed(BOB), claim),
+			Error::<Test>::OfferNotExist
+		);
+		System::assert_last_event(Event::PoeModule(crate::Event::OfferCancelled(ALICE, claim)));
+	});
+}
+
+#[test]
+fn offer_should_expire() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+
+		run_to_block(5);
+		assert!(PoeModule::offers(&claim).is_some());
+
+		run_to_block(6);
+		assert_eq!(PoeModule::offers(&claim), None);
+		System::assert_last_event(Event::PoeModule(crate::Event::OfferExpired(claim)));
+		assert_noop!(
+			PoeModule::accept_claim(Origin::signed(BOB), claim),
+			Error::<Test>::OfferNotExist
+		);
+
+		// a new offer can be made once the old one expired
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+		assert_eq!(PoeModule::offers(&claim).map(|offer| offer.expiry), Some(11));
+	});
+}
+
+#[test]
+fn revoked_claim_should_drop_offer() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(ALICE), claim, BOB));
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claim));
+
+		assert_eq!(PoeModule::offers(&claim), None);
+		assert_noop!(
+			PoeModule::accept_claim(Origin::signed(BOB), claim),
+			Error::<Test>::OfferNotExist
+		);
+	});
+}
+
+#[test]
+fn approve_claim_should_finalize_at_threshold() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::propose_claim(
+			Origin::signed(ALICE),
+			claim,
+			vec![BOB, COCO, ALICE],
+			2,
+			metadata(),
+		));
+		assert_eq!(Balances::reserved_balance(ALICE), DEPOSIT);
+		let proposed = crate::Event::ClaimProposed(ALICE, claim, 2, 6);
+		System::assert_last_event(Event::PoeModule(proposed));
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(BOB), claim, metadata(), None),
+			Error::<Test>::ClaimPending
+		);
+
+		run_to_block(3);
+		assert_ok!(PoeModule::approve_claim(Origin::signed(COCO), claim));
+		assert_eq!(PoeModule::proofs(&claim), None);
+		assert_noop!(
+			PoeModule::approve_claim(Origin::signed(COCO), claim),
+			Error::<Test>::AlreadyApproved
+		);
+
+		Timestamp::set_timestamp(NOW + 12_000);
+		assert_ok!(PoeModule::approve_claim(Origin::signed(BOB), claim));
+		assert_eq!(PoeModule::pending_claims(&claim), None);
+		assert!(!PendingExpiring::<Test>::contains_key(6));
+		assert_eq!(
+			PoeModule::proofs(&claim),
+			Some(Proof {
+				owner: ALICE,
+				block_number: 3,
+				algorithm: HashAlgorithm::Blake2_256,
+				deposit: DEPOSIT,
+			}),
+		);
+		assert_eq!(PoeModule::claim_info(&claim).map(|info| info.created_at), Some(NOW + 12_000));
+		assert_eq!(Balances::reserved_balance(ALICE), DEPOSIT);
+		System::assert_has_event(Event::PoeModule(crate::Event::ClaimApproved(BOB, claim, 2)));
+		System::assert_last_event(Event::PoeModule(crate::Event::ClaimCreate(
+			ALICE,
+			claim,
+			HashAlgorithm::Blake2_256,
+			DEPOSIT,
+		)));
+
+		// the finalized claim no longer expires
+		run_to_block(6);
+		assert!(PoeModule::proofs(&claim).is_some());
+	});
+}
+
+#[test]
+fn propose_claim_error_by_invalid_signers() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), claim, vec![BOB, BOB], 1, metadata()),
+			Error::<Test>::DuplicateSigner
+		);
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), claim, vec![BOB, COCO], 0, metadata()),
+			Error::<Test>::InvalidThreshold
+		);
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), claim, vec![BOB, COCO], 3, metadata()),
+			Error::<Test>::InvalidThreshold
+		);
+		let signers = (4..8).map(|byte| AccountId::new([byte; 32])).collect::<Vec<_>>();
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), claim, signers, 2, metadata()),
+			Error::<Test>::TooManySigners
+		);
+		// the signers are bounded before they are checked for duplicates
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), claim, vec![BOB; 4], 2, metadata()),
+			Error::<Test>::TooManySigners
+		);
+
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(BOB), claim, vec![ALICE], 1, metadata()),
+			Error::<Test>::ProofAlreadyExist
+		);
+	});
+}
+
+#[test]
+fn propose_claim_error_by_too_many_claims() {
+	new_test_ext().execute_with(|| {
+		for byte in 1..=3 {
+			let claim = H256::repeat_byte(byte);
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		}
+		let claim = H256::repeat_byte(4);
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), claim, vec![BOB], 1, metadata()),
+			Error::<Test>::TooManyClaims
+		);
+	});
+}
+
+#[test]
+fn approve_claim_error_by_too_many_claims() {
+	new_test_ext().execute_with(|| {
+		let pending = H256::repeat_byte(4);
+		assert_ok!(PoeModule::propose_claim(
+			Origin::signed(ALICE),
+			pending,
+			vec![BOB],
+			1,
+			metadata(),
+		));
+		for byte in 1..=3 {
+			let claim = H256::repeat_byte(byte);
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		}
+
+		// the proposer filled its index since proposing
+		assert_noop!(
+			PoeModule::approve_claim(Origin::signed(BOB), pending),
+			Error::<Test>::TooManyClaims
+		);
+		assert!(PoeModule::pending_claims(&pending).is_some());
+	});
+}
+
+#[test]
+fn finalized_claims_should_free_pending_slots() {
+	new_test_ext().execute_with(|| {
+		let claims: Vec<H256> = (1..=3).map(H256::repeat_byte).collect();
+		for claim in claims[..2].iter() {
+			assert_ok!(PoeModule::propose_claim(
+				Origin::signed(ALICE),
+				*claim,
+				vec![BOB],
+				1,
+				metadata(),
+			));
+		}
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), claims[2], vec![BOB], 1, metadata()),
+			Error::<Test>::TooManyPendingExpiring
+		);
+
+		assert_ok!(PoeModule::approve_claim(Origin::signed(BOB), claims[0]));
+		assert_eq!(PendingExpiring::<Test>::get(6).into_inner(), vec![claims[1]]);
+		assert_ok!(PoeModule::propose_claim(
+			Origin::signed(ALICE),
+			claims[2],
+			vec![BOB],
+			1,
+			metadata(),
+		));
+	});
+}
+
+#[test]
+fn approve_claim_error_by_not_signer() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_noop!(
+			PoeModule::approve_claim(Origin::signed(BOB), claim),
+			Error::<Test>::PendingClaimNotExist
+		);
+		let signers = vec![BOB];
+		assert_ok!(PoeModule::propose_claim(Origin::signed(ALICE), claim, signers, 1, metadata()));
+		assert_noop!(
+			PoeModule::approve_claim(Origin::signed(ALICE), claim),
+			Error::<Test>::NotSigner
+		);
+	});
+}
+
+#[test]
+fn pending_claim_should_expire() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::propose_claim(
+			Origin::signed(ALICE),
+			claim,
+			vec![BOB, COCO],
+			2,
+			metadata(),
+		));
+		assert_ok!(PoeModule::approve_claim(Origin::signed(BOB), claim));
+
+		run_to_block(6);
+		assert_eq!(PoeModule::pending_claims(&claim), None);
+		assert_eq!(PoeModule::proofs(&claim), None);
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		let expired = crate::Event::PendingClaimExpired(claim, DEPOSIT);
+		System::assert_last_event(Event::PoeModule(expired));
+		assert_noop!(
+			PoeModule::approve_claim(Origin::signed(COCO), claim),
+			Error::<Test>::PendingClaimNotExist
+		);
+	});
+}
+
+#[test]
+fn endorse_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		let statement = H256::repeat_byte(9);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::endorse(Origin::signed(BOB), claim, statement));
+
+		assert_eq!(
+			PoeModule::endorsements(&claim),
+			vec![Endorsement {
+				endorser: BOB,
+				statement,
+				block_number: 1,
+				verified: false,
+				deposit: 50,
+			}]
+		);
+		assert_eq!(Balances::reserved_balance(BOB), 50);
+		let endorsed = crate::Event::ClaimEndorsed(BOB, claim, statement, false);
+		System::assert_last_event(Event::PoeModule(endorsed));
+	});
+}
+
+#[test]
+fn endorse_error_by_invalid_endorsement() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		let statement = H256::repeat_byte(9);
+		assert_noop!(
+			PoeModule::endorse(Origin::signed(BOB), claim, statement),
+			Error::<Test>::ClaimNotExist
+		);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(
+			PoeModule::endorse(Origin::signed(ALICE), claim, statement),
+			Error::<Test>::CannotEndorseOwnClaim
+		);
+		assert_ok!(PoeModule::endorse(Origin::signed(BOB), claim, statement));
+		assert_noop!(
+			PoeModule::endorse(Origin::signed(BOB), claim, statement),
+			Error::<Test>::AlreadyEndorsed
+		);
+		assert_noop!(
+			PoeModule::endorse(Origin::signed(COCO), claim, statement),
+			Error::<Test>::TooManyEndorsements
+		);
+	});
+}
+
+#[test]
+fn endorse_should_mark_trusted_attestor() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(PoeModule::add_attestor(Origin::signed(ALICE), BOB), DispatchError::BadOrigin);
+		assert_ok!(PoeModule::add_attestor(Origin::root(), BOB));
+		assert_noop!(PoeModule::add_attestor(Origin::root(), BOB), Error::<Test>::AlreadyAttestor);
+
+		assert_ok!(PoeModule::endorse(Origin::signed(BOB), claim, H256::repeat_byte(9)));
+		assert_ok!(PoeModule::remove_attestor(Origin::root(), BOB));
+		assert_noop!(PoeModule::remove_attestor(Origin::root(), BOB), Error::<Test>::NotAttestor);
+
+		// the endorsement is no longer shown as verified once the attestor is removed
+		assert_eq!(PoeModule::attestors(BOB), None);
+		assert!(!PoeModule::endorsements(&claim)[0].verified);
+		assert_ok!(PoeModule::add_attestor(Origin::root(), BOB));
+		assert!(PoeModule::endorsements(&claim)[0].verified);
+	});
+}
+
+#[test]
+fn revoke_endorsement_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::endorse(Origin::signed(BOB), claim, H256::repeat_byte(9)));
+		assert_noop!(
+			PoeModule::revoke_endorsement(Origin::signed(ALICE), claim),
+			Error::<Test>::EndorsementNotExist
+		);
+		assert_ok!(PoeModule::revoke_endorsement(Origin::signed(BOB), claim));
+
+		assert!(PoeModule::endorsements(&claim).is_empty());
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		System::assert_last_event(Event::PoeModule(crate::Event::EndorsementRevoked(BOB, claim)));
+	});
+}
+
+#[test]
+fn remove_endorsement_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::endorse(Origin::signed(BOB), claim, H256::repeat_byte(9)));
+		assert_noop!(
+			PoeModule::remove_endorsement(Origin::signed(BOB), claim, BOB),
+			Error::<Test>::NotClaimOwner
+		);
+		assert_noop!(
+			PoeModule::remove_endorsement(Origin::signed(ALICE), claim, COCO),
+			Error::<Test>::EndorsementNotExist
+		);
+		assert_ok!(PoeModule::remove_endorsement(Origin::signed(ALICE), claim, BOB));
+
+		assert!(PoeModule::endorsements(&claim).is_empty());
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		let removed = crate::Event::EndorsementRemoved(ALICE, BOB, claim);
+		System::assert_last_event(Event::PoeModule(removed));
+	});
+}
+
+#[test]
+fn revoked_claim_should_refund_endorsements() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_ok!(PoeModule::endorse(Origin::signed(BOB), claim, H256::repeat_byte(9)));
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claim));
+
+		assert!(PoeModule::endorsements(&claim).is_empty());
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+	});
+}
+
+#[test]
+fn claim_should_expire() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_eq!(PoeModule::next_claim_expiry_block(), None);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), Some(5)));
+		assert_ok!(PoeModule::endorse(Origin::signed(BOB), claim, H256::repeat_byte(9)));
+		assert_eq!(PoeModule::claim_expiry(&claim), Some(5));
+		assert_eq!(PoeModule::next_claim_expiry_block(), Some(1));
+
+		run_to_block(4);
+		assert!(PoeModule::proofs(&claim).is_some());
+
+		run_to_block(5);
+		assert_eq!(PoeModule::proofs(&claim), None);
+		assert_eq!(PoeModule::claim_info(&claim), None);
+		assert_eq!(PoeModule::claim_expiry(&claim), None);
+		assert!(PoeModule::endorsements(&claim).is_empty());
+		assert_eq!(PoeModule::next_claim_expiry_block(), Some(6));
+		assert_eq!(Balances::reserved_balance(ALICE), 0);
+		assert_eq!(Balances::reserved_balance(BOB), 0);
+		assert_eq!(PoeModule::holder_at(claim, 5), None);
+		System::assert_last_event(Event::PoeModule(crate::Event::ClaimExpired(
+			ALICE,
+			claim,
+			DEPOSIT,
+		)));
+
+		// the key is free again
+		assert_ok!(PoeModule::create_claim(Origin::signed(BOB), claim, metadata(), None));
+	});
+}
+
+#[test]
+fn claims_should_expire_partially_in_idle_time() {
+	new_test_ext().execute_with(|| {
+		let claims: Vec<H256> = (1..=2).map(H256::repeat_byte).collect();
+		for claim in claims.iter() {
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), *claim, metadata(), Some(5)));
+		}
+		PoeModule::on_idle(4, u64::MAX);
+		assert_eq!(PoeModule::next_claim_expiry_block(), Some(5));
+
+		// enough weight for a single claim: 2 + 3 to take the block and 17 to remove a claim
+		System::set_block_number(5);
+		PoeModule::on_idle(5, 22);
+
+		assert_eq!(PoeModule::proofs(&claims[1]), None);
+		assert!(PoeModule::proofs(&claims[0]).is_some());
+		assert_eq!(ClaimsExpiring::<Test>::get(5).into_inner(), vec![claims[0]]);
+		assert_eq!(PoeModule::next_claim_expiry_block(), Some(5));
+
+		PoeModule::on_idle(5, u64::MAX);
+		assert_eq!(PoeModule::proofs(&claims[0]), None);
+		assert_eq!(PoeModule::next_claim_expiry_block(), Some(6));
+	});
+}
+
+#[test]
+fn create_claim_error_by_invalid_expiry() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), Some(1)),
+			Error::<Test>::ClaimExpiryInPast
+		);
+		for byte in 1..=2 {
+			let claim = H256::repeat_byte(byte);
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), Some(5)));
+		}
+		let claim = H256::repeat_byte(3);
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), Some(5)),
+			Error::<Test>::TooManyClaimsExpiring
+		);
+	});
+}
+
+#[test]
+fn renew_claim_should_work() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), Some(5)));
+		let issuance = Balances::total_issuance();
+		assert_ok!(PoeModule::renew_claim(Origin::signed(ALICE), claim, 10));
+
+		assert_eq!(PoeModule::claim_expiry(&claim), Some(15));
+		assert!(!ClaimsExpiring::<Test>::contains_key(5));
+		assert_eq!(ClaimsExpiring::<Test>::get(15).into_inner(), vec![claim]);
+		assert_eq!(Balances::free_balance(ALICE), 10000 - DEPOSIT - 20);
+		assert_eq!(Balances::total_issuance(), issuance - 20);
+		let renewed = crate::Event::ClaimRenewed(ALICE, claim, 15, 20);
+		System::assert_last_event(Event::PoeModule(renewed));
+
+		run_to_block(14);
+		assert!(PoeModule::proofs(&claim).is_some());
+		run_to_block(15);
+		assert_eq!(PoeModule::proofs(&claim), None);
+	});
+}
+
+#[test]
+fn renew_claim_error_by_invalid_claim() {
+	new_test_ext().execute_with(|| {
+		let claim = H256::repeat_byte(1);
+		assert_noop!(
+			PoeModule::renew_claim(Origin::signed(ALICE), claim, 10),
+			Error::<Test>::ClaimNotExist
+		);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		assert_noop!(
+			PoeModule::renew_claim(Origin::signed(ALICE), claim, 10),
+			Error::<Test>::ClaimNeverExpires
+		);
+
+		let expiring = H256::repeat_byte(2);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), expiring, metadata(), Some(5)));
+		assert_noop!(
+			PoeModule::renew_claim(Origin::signed(ALICE), expiring, 0),
+			Error::<Test>::InvalidRenewalPeriod
+		);
+		assert_noop!(
+			PoeModule::renew_claim(Origin::signed(BOB), expiring, 10),
+			Error::<Test>::NotClaimOwner
+		);
+		assert_noop!(
+			PoeModule::renew_claim(Origin::signed(ALICE), expiring, 5000),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+	});
+}
+
+#[test]
+fn removed_claims_should_free_expiring_slots() {
+	new_test_ext().execute_with(|| {
+		let claims: Vec<H256> = (1..=3).map(H256::repeat_byte).collect();
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claims[0], metadata(), Some(5)));
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claims[1], metadata(), Some(5)));
+
+		// renewed and revoked claims both give their slot back
+		assert_ok!(PoeModule::renew_claim(Origin::signed(ALICE), claims[0], 1));
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), claims[1]));
+		assert!(!ClaimsExpiring::<Test>::contains_key(5));
+
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claims[1], metadata(), Some(5)));
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claims[2], metadata(), Some(5)));
+	});
+}
+
+fn digests(count: u8) -> Vec<H256> {
+	(0..count).map(|byte| H256(sp_io::hashing::blake2_256(&[byte]))).collect()
+}
+
+#[test]
+fn merkle_proof_should_verify_every_leaf() {
+	for count in 1..=9 {
+		let leaves = digests(count);
+		let tree = merkle::MerkleTree::new(&leaves).unwrap();
+		assert_eq!(tree.leaf_count(), count as u32);
+		for (index, leaf) in leaves.iter().enumerate() {
+			let proof = tree.proof(index as u32).unwrap();
+			let index = index as u32;
+			assert!(merkle::verify_proof(&tree.root(), leaf, index, tree.leaf_count(), &proof));
+		}
+		assert_eq!(tree.proof(count as u32), None);
+	}
+	assert!(merkle::MerkleTree::new(&[]).is_none());
+}
+
+#[test]
+fn merkle_proof_error_by_wrong_leaf() {
+	let leaves = digests(5);
+	let tree = merkle::MerkleTree::new(&leaves).unwrap();
+	let (root, proof) = (tree.root(), tree.proof(2).unwrap());
+
+	assert!(merkle::verify_proof(&root, &leaves[2], 2, 5, &proof));
+	assert!(!merkle::verify_proof(&root, &leaves[3], 2, 5, &proof));
+	assert!(!merkle::verify_proof(&root, &leaves[2], 3, 5, &proof));
+	assert!(!merkle::verify_proof(&root, &leaves[2], 2, 6, &proof));
+	assert!(!merkle::verify_proof(&root, &leaves[2], 5, 5, &proof));
+	assert!(!merkle::verify_proof(&root, &leaves[2], 2, 5, &proof[1..]));
+	let mut longer = proof.clone();
+	longer.push(root);
+	assert!(!merkle::verify_proof(&root, &leaves[2], 2, 5, &longer));
+
+	// an inner node does not pass for a leaf of a smaller tree
+	let hashed = leaves.iter().map(merkle::hash_leaf).collect::<Vec<_>>();
+	let (node, sibling) =
+		(merkle::hash_node(&hashed[0], &hashed[1]), merkle::hash_node(&hashed[2], &hashed[3]));
+	assert_eq!(merkle::hash_node(&merkle::hash_node(&node, &sibling), &hashed[4]), root);
+	assert!(!merkle::verify_proof(&root, &node, 0, 3, &[sibling, hashed[4]]));
+}
+
+#[test]
+fn anchor_batch_should_work() {
+	new_test_ext().execute_with(|| {
+		let leaves = digests(5);
+		let tree = merkle::MerkleTree::new(&leaves).unwrap();
+		let root = tree.root();
+		assert_ok!(PoeModule::anchor_batch(Origin::signed(ALICE), root, 5, metadata()));
+
+		assert_eq!(PoeModule::proofs(&root).map(|proof| proof.owner), Some(ALICE));
+		assert_eq!(PoeModule::batch_leaf_count(root), Some(5));
+		System::assert_last_event(Event::PoeModule(crate::Event::BatchAnchored(ALICE, root, 5)));
+
+		let proof = tree.proof(4).unwrap();
+		assert!(PoeModule::verify_inclusion(root, leaves[4], 4, proof.clone()));
+		assert!(!PoeModule::verify_inclusion(root, leaves[3], 4, proof.clone()));
+
+		// the batch is gone with its claim
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), root));
+		assert_eq!(PoeModule::batch_leaf_count(root), None);
+		assert!(!PoeModule::verify_inclusion(root, leaves[4], 4, proof));
+	});
+}
+
+#[test]
+fn anchor_batch_error_by_invalid_batch() {
+	new_test_ext().execute_with(|| {
+		let root = H256::repeat_byte(1);
+		assert_noop!(
+			PoeModule::anchor_batch(Origin::signed(ALICE), root, 0, metadata()),
+			Error::<Test>::InvalidLeafCount
+		);
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), root, metadata(), None));
+		assert_noop!(
+			PoeModule::anchor_batch(Origin::signed(BOB), root, 5, metadata()),
+			Error::<Test>::ProofAlreadyExist
+		);
+	});
+}
+
+/// Checks every claim is indexed under its owner, and nothing else is indexed.
+fn assert_owner_index_consistent() {
+	let mut indexed = 0;
+	for (owner, claims) in ClaimsOf::<Test>::iter() {
+		assert!(!claims.is_empty());
+		for claim in claims.iter() {
+			assert_eq!(PoeModule::proofs(claim).map(|proof| proof.owner), Some(owner.clone()));
+		}
+		indexed += claims.len();
+	}
+	assert_eq!(indexed, Proofs::<Test>::iter().count());
+}
+
+#[test]
+fn owner_index_should_follow_claims() {
+	new_test_ext().execute_with(|| {
+		let (first, second, third) =
+			(H256::repeat_byte(1), H256::repeat_byte(2), H256::repeat_byte(3));
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), first, metadata(), None));
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), second, metadata(), Some(5)));
+		assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), third, metadata(), None));
+		assert_eq!(PoeModule::claims_of(ALICE, 0, 10), vec![first, second, third]);
+		assert_owner_index_consistent();
+
+		transfer(ALICE, first, BOB);
+		assert_eq!(PoeModule::claims_of(ALICE, 0, 10), vec![second, third]);
+		assert_eq!(PoeModule::claims_of(BOB, 0, 10), vec![first]);
+		assert_owner_index_consistent();
+
+		transfer(BOB, first, ALICE);
+		assert_eq!(PoeModule::claims_of(ALICE, 0, 10), vec![second, third, first]);
+		assert_eq!(PoeModule::claims_of(BOB, 0, 10), vec![]);
+		assert!(!ClaimsOf::<Test>::contains_key(BOB));
+		assert_owner_index_consistent();
+
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(ALICE), third));
+		assert_eq!(PoeModule::claims_of(ALICE, 0, 10), vec![second, first]);
+		assert_owner_index_consistent();
+
+		run_to_block(5);
+		assert_eq!(PoeModule::claims_of(ALICE, 0, 10), vec![first]);
+		assert_owner_index_consistent();
+	});
+}
+
+#[test]
+fn claims_of_should_paginate() {
+	new_test_ext().execute_with(|| {
+		let claims = (1..=3).map(H256::repeat_byte).collect::<Vec<_>>();
+		for claim in claims.iter() {
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), *claim, metadata(), None));
+		}
+
+		assert_eq!(PoeModule::claims_of(ALICE, 0, 2), claims[..2].to_vec());
+		assert_eq!(PoeModule::claims_of(ALICE, 2, 2), claims[2..].to_vec());
+		assert_eq!(PoeModule::claims_of(ALICE, 3, 2), vec![]);
+		assert_eq!(PoeModule::claims_of(ALICE, 0, 0), vec![]);
+		assert_eq!(PoeModule::claims_of(COCO, 0, 2), vec![]);
+	});
+}
+
+#[test]
+fn owner_index_error_by_too_many_claims() {
+	new_test_ext().execute_with(|| {
+		for byte in 1..=3 {
+			let claim = H256::repeat_byte(byte);
+			assert_ok!(PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None));
+		}
+		let claim = H256::repeat_byte(4);
+		assert_noop!(
+			PoeModule::create_claim(Origin::signed(ALICE), claim, metadata(), None),
+			Error::<Test>::TooManyClaims
+		);
+		assert_noop!(
+			PoeModule::anchor_batch(Origin::signed(ALICE), claim, 2, metadata()),
+			Error::<Test>::TooManyClaims
+		);
+
+		// a full account cannot take over more claims either
+		assert_ok!(PoeModule::create_claim(Origin::signed(BOB), claim, metadata(), None));
+		assert_ok!(PoeModule::offer_claim(Origin::signed(BOB), claim, ALICE));
+		assert_noop!(
+			PoeModule::accept_claim(Origin::signed(ALICE), claim),
+			Error::<Test>::TooManyClaims
+		);
+
+		// nor propose a co-attested claim
+		let pending = H256::repeat_byte(5);
+		assert_noop!(
+			PoeModule::propose_claim(Origin::signed(ALICE), pending, vec![BOB], 1, metadata()),
+			Error::<Test>::TooManyClaims
+		);
+		assert_owner_index_consistent();
+	});
+}
+
+#[test]
+fn migration_should_move_proofs_to_digest_keys() {
+	new_test_ext().execute_with(|| {
+		// the first version of `Proofs` was keyed by the raw claim bytes
+		let digest = H256::repeat_byte(7);
+		let preimage = b"hello".to_vec();
+		for (key, owner) in [(digest.as_bytes().to_vec(), ALICE), (preimage.clone(), BOB)] {
+			let hash = Blake2_128Concat::hash(&key.encode());
+			put_storage_value(b"PoeModule", b"Proofs", &hash, (owner, 3u64));
+		}
+		StorageVersion::new(0).put::<PoeModule>();
+
+		PoeModule::on_runtime_upgrade();
+
+		assert_eq!(
+			PoeModule::proofs(&digest),
+			Some(Proof {
+				owner: ALICE,
+				block_number: 3,
+				algorithm: HashAlgorithm::Blake2_256,
+				deposit: 0
+			})
+		);
+		let claim = HashAlgorithm::Blake2_256.hash(&preimage);
+		assert_eq!(PoeModule::proofs(&claim).map(|proof| proof.owner), Some(BOB));
+		assert_eq!(PoeModule::claim_info(&claim).map(|info| info.version), Some(1));
+		assert_eq!(
+			PoeModule::provenance(claim),
+			vec![custody(BOB, 3, None, CustodyReason::Created)]
+		);
+		assert_eq!(PoeModule::owned_claims(ALICE).into_inner(), vec![digest]);
+		assert_owner_index_consistent();
+		assert_eq!(StorageVersion::get::<PoeModule>(), StorageVersion::new(1));
 
+		// a migrated claim behaves as any other
+		assert_ok!(PoeModule::revoked_claim(Origin::signed(BOB), claim));
 	});
 }
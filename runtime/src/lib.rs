@@ -15,7 +15,7 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -31,7 +31,7 @@ pub use frame_support::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
@@ -103,10 +103,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
 	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 };
 
 /// This determines the average expected block time that we are targeting.
@@ -187,7 +187,7 @@ impl frame_system::Config for Runtime {
 	/// What to do if a new account is created.
 	type OnNewAccount = ();
 	/// What to do if an account is fully reaped from the system.
-	type OnKilledAccount = ();
+	type OnKilledAccount = KittiesModule;
 	/// The data to be stored in an account.
 	type AccountData = pallet_balances::AccountData<Balance>;
 	/// Weight information for the extrinsics of this pallet.
@@ -286,20 +286,76 @@ impl pallet_template::Config for Runtime {
 
 
 parameter_types! {
-	pub const MaxLength: u32 = 6;
+	pub const MaxLength: u32 = 256;
+	pub const ClaimHashing: pallet_poe::HashAlgorithm = pallet_poe::HashAlgorithm::Blake2_256;
+	pub const ClaimDepositBase: Balance = 1000;
+	pub const ClaimDepositPerByte: Balance = 10;
+	pub const MaxTitleLength: u32 = 128;
+	pub const MaxContentTypeLength: u32 = 64;
+	pub const MaxUriLength: u32 = 256;
+	pub const MaxHistoryLength: u32 = 100;
+	pub const OfferPeriod: BlockNumber = DAYS;
+	pub const MaxOffersExpiringPerBlock: u32 = 100;
+	pub const MaxSigners: u32 = 16;
+	pub const PendingClaimPeriod: BlockNumber = 7 * DAYS;
+	pub const MaxPendingExpiringPerBlock: u32 = 100;
+	pub const MaxEndorsements: u32 = 32;
+	pub const EndorsementDeposit: Balance = 1_000_000_000_000;
+	pub const MaxClaimsExpiringPerBlock: u32 = 100;
+	pub const RenewalFeePerBlock: Balance = 1;
+	pub const MaxClaimsPerAccount: u32 = 1000;
 }
 
 /// Configure the pallet-template in pallets/template.
 impl pallet_poe::Config for Runtime {
 	type Event = Event;
 	type Length = MaxLength;
+	type ClaimHashing = ClaimHashing;
+	type Currency = Balances;
+	type ClaimDepositBase = ClaimDepositBase;
+	type ClaimDepositPerByte = ClaimDepositPerByte;
+	type Time = Timestamp;
+	type MaxTitleLength = MaxTitleLength;
+	type MaxContentTypeLength = MaxContentTypeLength;
+	type MaxUriLength = MaxUriLength;
+	type MaxHistoryLength = MaxHistoryLength;
+	type OfferPeriod = OfferPeriod;
+	type MaxOffersExpiringPerBlock = MaxOffersExpiringPerBlock;
+	type MaxSigners = MaxSigners;
+	type PendingClaimPeriod = PendingClaimPeriod;
+	type MaxPendingExpiringPerBlock = MaxPendingExpiringPerBlock;
+	type MaxEndorsements = MaxEndorsements;
+	type EndorsementDeposit = EndorsementDeposit;
+	type AttestorOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxClaimsExpiringPerBlock = MaxClaimsExpiringPerBlock;
+	type RenewalFeePerBlock = RenewalFeePerBlock;
+	type OnRenewalFee = ();
+	type MaxClaimsPerAccount = MaxClaimsPerAccount;
 }
 
 parameter_types! {
 	// One can owned at most 9,999 Kitties
 	pub const MaxKittyOwned: u32 = 9999;
 	pub const Pledge: u64 = 5000;
-	
+	pub const AdoptionPoolId: PalletId = PalletId(*b"py/kpool");
+	pub const RepriceReward: u64 = 10;
+	pub const MinRaffleTickets: u32 = 2;
+	// Settling a block's raffles moves at most 400 ticket prices in `on_initialize`.
+	pub const MaxRaffleTickets: u32 = 100;
+	pub const MaxRafflesPerBlock: u32 = 4;
+	pub const ChallengeDeposit: u64 = 100;
+	pub const MaxChallengesPerKitty: u32 = 16;
+	pub const MaxTournamentEntrants: u32 = 64;
+	pub const ExperiencePerBlock: u64 = 1;
+	pub LevelThresholds: Vec<u64> =
+		vec![HOURS as u64, DAYS as u64, 7 * DAYS as u64, 30 * DAYS as u64];
+	pub const BreedCooldown: BlockNumber = HOURS;
+	pub const MaxListingsExpiringPerBlock: u32 = 100;
+	pub const MaxSalesPerKitty: u32 = 10;
+	pub const MaxRecentTrades: u32 = 100;
+	pub const KittiesUnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+	pub const MaxGiftsExpiringPerBlock: u32 = 100;
+	pub const MaxGiftClaimsPerBlock: u32 = 10;
 }
 
 
@@ -309,7 +365,33 @@ impl pallet_kitties::Config for Runtime {
 	type KittyRandomness = RandomnessCollectiveFlip;
 	type KittyIndex = u64;
 	type Pledge = Pledge;
+	type PledgeOrigin = frame_system::EnsureRoot<AccountId>;
+	type ForceOrigin = frame_system::EnsureRoot<AccountId>;
+	type AdoptionPoolId = AdoptionPoolId;
+	type RepriceReward = RepriceReward;
 	type MaxKittyOwned = MaxKittyOwned;
+	type MinRaffleTickets = MinRaffleTickets;
+	type MaxRaffleTickets = MaxRaffleTickets;
+	type MaxRafflesPerBlock = MaxRafflesPerBlock;
+	type ChallengeDeposit = ChallengeDeposit;
+	type MaxChallengesPerKitty = MaxChallengesPerKitty;
+	type TournamentOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxTournamentEntrants = MaxTournamentEntrants;
+	type ExperiencePerBlock = ExperiencePerBlock;
+	type LevelThresholds = LevelThresholds;
+	type BreedCooldown = BreedCooldown;
+	type MaxListingsExpiringPerBlock = MaxListingsExpiringPerBlock;
+	type MaxSalesPerKitty = MaxSalesPerKitty;
+	type MaxRecentTrades = MaxRecentTrades;
+	type PermitSignature = Signature;
+	type PermitSigner = <Signature as Verify>::Signer;
+	type UnsignedPriority = KittiesUnsignedPriority;
+	type MaxGiftsExpiringPerBlock = MaxGiftsExpiringPerBlock;
+	type MaxGiftClaimsPerBlock = MaxGiftClaimsPerBlock;
+	type OnKittyMinted = ();
+	type OnKittyTransferred = ();
+	type OnKittyBurned = ();
+	type OnKittyBred = ();
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -477,6 +559,30 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_kitties_runtime_api::KittiesApi<Block, BlockNumber, Balance> for Runtime {
+		fn market_stats(window: BlockNumber) -> pallet_kitties::MarketStats<Balance> {
+			KittiesModule::market_stats(window)
+		}
+	}
+
+	impl pallet_poe_runtime_api::PoeApi<Block, AccountId, BlockNumber> for Runtime {
+		fn provenance(claim: Hash) -> Vec<pallet_poe::Custody<AccountId, BlockNumber>> {
+			PoeModule::provenance(claim)
+		}
+
+		fn holder_at(claim: Hash, at: BlockNumber) -> Option<AccountId> {
+			PoeModule::holder_at(claim, at)
+		}
+
+		fn verify_inclusion(root: Hash, leaf: Hash, index: u32, proof: Vec<Hash>) -> bool {
+			PoeModule::verify_inclusion(root, leaf, index, proof)
+		}
+
+		fn claims_of(owner: AccountId, start: u32, limit: u32) -> Vec<Hash> {
+			PoeModule::claims_of(owner, start, limit)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance> for Runtime {
 		fn query_info(
 			uxt: <Block as BlockT>::Extrinsic,
@@ -492,6 +598,19 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[cfg(feature = "try-runtime")]
+	impl frame_try_runtime::TryRuntime<Block> for Runtime {
+		fn on_runtime_upgrade() -> (Weight, Weight) {
+			// Fail loudly if any post-upgrade check, e.g. the kitties invariants, does not hold.
+			let weight = Executive::try_runtime_upgrade().unwrap();
+			(weight, BlockWeights::get().max_block)
+		}
+
+		fn execute_block_no_check(block: Block) -> Weight {
+			Executive::execute_block_no_check(block)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (